@@ -0,0 +1,61 @@
+extern crate criterion;
+extern crate sp800_185;
+
+use criterion::{ Criterion, Throughput, BenchmarkId, criterion_group, criterion_main };
+use sp800_185::{ ParallelHash, Backend };
+
+
+fn bench_update(c: &mut Criterion) {
+    let input = vec![0x5a; 16 << 20];
+
+    #[allow(unused_mut)]
+    let mut backends = vec![Backend::Sequential];
+    #[cfg(feature = "std")] backends.push(Backend::Threads);
+    #[cfg(feature = "rayon")] backends.push(Backend::Rayon);
+
+    let mut group = c.benchmark_group("parallelhash256");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(10);
+
+    for &backend in &backends {
+        for &blocksize in &[1024, 8192] {
+            let id = BenchmarkId::new(format!("{:?}", backend), blocksize);
+            group.bench_with_input(id, &blocksize, |b, &blocksize| b.iter(|| {
+                let mut buf = [0; 64];
+                let mut hasher = ParallelHash::new_parallelhash256(b"", blocksize)
+                    .with_backend(backend);
+                hasher.update(&input);
+                hasher.finalize(&mut buf);
+                buf
+            }));
+        }
+    }
+
+    group.finish();
+}
+
+fn bench_small_updates(c: &mut Criterion) {
+    let input = vec![0x5a; 1 << 20];
+
+    let mut group = c.benchmark_group("parallelhash256_small_updates");
+    group.throughput(Throughput::Bytes(input.len() as u64));
+    group.sample_size(10);
+
+    for &size in &[100, 4096] {
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| b.iter(|| {
+            let mut buf = [0; 64];
+            let mut hasher = ParallelHash::new_parallelhash256(b"", 1024)
+                .with_backend(Backend::Sequential);
+            for chunk in input.chunks(size) {
+                hasher.update(chunk);
+            }
+            hasher.finalize(&mut buf);
+            buf
+        }));
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_update, bench_small_updates);
+criterion_main!(benches);
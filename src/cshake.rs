@@ -1,5 +1,9 @@
-use tiny_keccak::{ Keccak, XofReader };
+use core::mem;
+use ::sponge::{ Sponge, XofStream };
+use ::prefix::Prefix;
 use ::utils::left_encode;
+use ::recorder::Tracker;
+use ::midstate::{ self, MidstateError, SPONGE_LEN, MIDSTATE_LEN, KIND_CSHAKE };
 
 
 /// The customizable SHAKE function.
@@ -8,54 +12,195 @@ use ::utils::left_encode;
 /// `SHAKE` and `KECCAK[c]` functions specified in FIPS 202. `cSHAKE128` provides a 128-bit
 /// security strength, while `cSHAKE256` provides a 256-bit security strength.
 #[derive(Clone)]
-pub struct CShake(pub(crate) Keccak);
+pub struct CShake {
+    inner: Sponge,
+    init: Sponge,
+    trace: Tracker
+}
 
 impl CShake {
     #[inline]
     pub fn new_cshake128(name: &[u8], custom: &[u8]) -> Self {
-        let mut cshake = CShake(Keccak::new(168, 0x04));
+        let mut cshake = CShake {
+            inner: Sponge::new(168, 0x04),
+            init: Sponge::new(168, 0x04),
+            trace: Tracker::new(168)
+        };
         cshake.init(name, custom, 168);
         cshake
     }
 
     #[inline]
     pub fn new_cshake256(name: &[u8], custom: &[u8]) -> Self {
-        let mut cshake = CShake(Keccak::new(136, 0x04));
+        let mut cshake = CShake {
+            inner: Sponge::new(136, 0x04),
+            init: Sponge::new(136, 0x04),
+            trace: Tracker::new(136)
+        };
         cshake.init(name, custom, 136);
         cshake
     }
 
+    /// `cSHAKE` from a precomputed `Prefix`, without permuting.
+    pub fn from_prefix(prefix: &Prefix) -> Self {
+        let rate = prefix.sponge.rate();
+
+        // the recorder sees the prefix only when it is absorbed
+        if cfg!(feature = "recorder") {
+            return if rate == 168 {
+                CShake::new_cshake128(prefix.name, prefix.custom)
+            } else {
+                CShake::new_cshake256(prefix.name, prefix.custom)
+            };
+        }
+
+        CShake {
+            inner: prefix.sponge.clone(),
+            init: prefix.sponge.clone(),
+            trace: Tracker::new(rate)
+        }
+    }
+
+    /// Rate in bytes.
+    #[inline]
+    pub(crate) fn rate(&self) -> usize {
+        self.inner.rate()
+    }
+
     fn init(&mut self, name: &[u8], custom: &[u8], rate: usize) {
         let mut encbuf = [0; 9];
 
         let pos = left_encode(&mut encbuf, rate as u64);
-        self.0.absorb(&encbuf[pos..]); // left_encode(rate)
-
-        let pos = left_encode(&mut encbuf, name.len() as u64 * 8);
-        self.0.absorb(&encbuf[pos..]); // left_encode(len(N))
-        self.0.absorb(name);
+        self.absorb("left_encode(rate)", &encbuf[pos..]);
 
-        let pos = left_encode(&mut encbuf, custom.len() as u64 * 8);
-        self.0.absorb(&encbuf[pos..]); // left_encode(len(S))
-        self.0.absorb(custom);
+        self.absorb_string("encode_string(N)", name);
+        self.absorb_string("encode_string(S)", custom);
 
-        self.0.fill_block(); // pad zero
+        self.fill_block(); // pad zero
+        self.set_init();
     }
 
     #[inline]
     pub fn update(&mut self, buf: &[u8]) {
-        self.0.absorb(buf)
+        self.absorb("X", buf)
     }
 
     #[inline]
     pub fn finalize(&mut self, buf: &mut [u8]) {
-        self.0.pad();
-        self.0.keccakf();
-        self.0.squeeze(buf);
+        self.trace.pad(0x04);
+        self.inner.clone().finalize(buf);
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.trace.pad(0x04);
+        let inner = mem::replace(&mut self.inner, self.init.clone());
+        self.trace = Tracker::resume(self.init.rate(), self.init.offset());
+        inner.finalize(buf);
+    }
+
+    /// Back to the state after `bytepad(encode_string(N) || encode_string(S))`.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.inner = self.init.clone();
+        self.trace = Tracker::resume(self.init.rate(), self.init.offset());
+    }
+
+    /// Makes the current state the one `reset` returns to, as the state after the key of `KMAC`.
+    #[inline]
+    pub(crate) fn set_init(&mut self) {
+        self.init = self.inner.clone();
+    }
+
+    #[inline]
+    pub fn xof(mut self) -> XofStream {
+        self.trace.pad(0x04);
+        self.inner.xof()
+    }
+
+    /// The state, to resume hashing with `import_midstate`.
+    pub fn export_midstate(&self) -> [u8; MIDSTATE_LEN] {
+        self.export_kind(KIND_CSHAKE)
+    }
+
+    pub fn import_midstate(buf: &[u8]) -> Result<Self, MidstateError> {
+        CShake::import_kind(buf, KIND_CSHAKE)
+    }
+
+    pub(crate) fn export_kind(&self, kind: u8) -> [u8; MIDSTATE_LEN] {
+        let mut out = [0; MIDSTATE_LEN];
+        midstate::write_header(&mut out, kind);
+        self.export_sponge(&mut out[2..]);
+        out
+    }
+
+    pub(crate) fn import_kind(buf: &[u8], kind: u8) -> Result<Self, MidstateError> {
+        let body = midstate::check_header(buf, kind, MIDSTATE_LEN)?;
+        CShake::import_sponge(body)
+    }
+
+    /// Writes the current sponge, then the one `reset` returns to, `2 * SPONGE_LEN` bytes.
+    #[inline]
+    pub(crate) fn export_sponge(&self, out: &mut [u8]) {
+        let (inner, init) = out.split_at_mut(SPONGE_LEN);
+        self.inner.export(inner);
+        self.init.export(init);
+    }
+
+    pub(crate) fn import_sponge(buf: &[u8]) -> Result<Self, MidstateError> {
+        if buf.len() != 2 * SPONGE_LEN {
+            return Err(MidstateError::Length);
+        }
+
+        let (inner, init) = buf.split_at(SPONGE_LEN);
+        let inner = Sponge::import(inner, 0x04, 24)?;
+        let init = Sponge::import(init, 0x04, 24)?;
+        if (inner.rate() != 168 && inner.rate() != 136) || init.rate() != inner.rate() {
+            return Err(MidstateError::Invalid);
+        }
+
+        Ok(CShake {
+            trace: Tracker::resume(inner.rate(), inner.offset()),
+            inner,
+            init
+        })
+    }
+
+    /// The sponge after `bytepad(encode_string(N) || encode_string(S))` and what was absorbed.
+    #[inline]
+    pub(crate) fn into_sponge(self) -> Sponge {
+        self.inner
+    }
+
+    /// Absorbs one labeled segment.
+    #[inline]
+    pub(crate) fn absorb(&mut self, label: &'static str, buf: &[u8]) {
+        self.trace.absorb(label, buf);
+        self.inner.update(buf);
+    }
+
+    /// Absorbs the continuation of the last segment.
+    #[inline]
+    pub(crate) fn absorb_more(&mut self, buf: &[u8]) {
+        self.trace.absorb_more(buf);
+        self.inner.update(buf);
+    }
+
+    /// Absorbs `encode_string(buf)` as one segment.
+    #[inline]
+    pub(crate) fn absorb_string(&mut self, label: &'static str, buf: &[u8]) {
+        let mut encbuf = [0; 9];
+
+        let pos = left_encode(&mut encbuf, buf.len() as u64 * 8);
+        self.absorb(label, &encbuf[pos..]);
+        self.absorb_more(buf);
     }
 
+    /// Zero-fills the rest of the block, the tail of `bytepad`.
     #[inline]
-    pub fn xof(self) -> XofReader {
-        self.0.xof()
+    pub(crate) fn fill_block(&mut self) {
+        self.trace.fill_block();
+        self.inner.fill_block();
     }
 }
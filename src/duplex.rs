@@ -0,0 +1,132 @@
+use ::sponge::Sponge;
+use ::cshake::CShake;
+use ::utils::verify_tag;
+
+
+/// Frame byte ending a run of `absorb`, as the `cSHAKE` suffix.
+const ABSORB: u8 = 0x04;
+/// Frame byte ending a run of `encrypt` or `decrypt`.
+const CRYPT: u8 = 0x05;
+
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Phase {
+    Absorb,
+    Crypt,
+    Squeeze
+}
+
+/// Duplex sponge on the `cSHAKE` permutation, alternating absorbing and squeezing.
+///
+/// The state starts as `cSHAKE` does, with `bytepad(encode_string(N) || encode_string(S), rate)`.
+/// Calls of the same kind are one continuous phase: `absorb(a); absorb(b)` is `absorb(a || b)`,
+/// and `squeeze` continues the output. Moving to another phase ends the current one:
+///
+/// * absorbed bytes are padded with `0x04` and `pad10*1`, then permuted, so that absorbing `X`
+///   then squeezing `L` bytes is `cSHAKE(X, L, N, S)`;
+/// * encrypted or decrypted bytes are padded with `0x05` and `pad10*1`, then permuted;
+/// * squeezing only permutes, dropping the rest of the block.
+///
+/// The lengths of the squeezed outputs are not bound to the state. Protocols that absorb
+/// variable-length values one after another should frame them, as with `encode_string`.
+///
+/// `encrypt` and `decrypt` are the duplex of SpongeWrap: the key stream is the rate, and the
+/// ciphertext replaces it. An AEAD absorbs the key, the nonce and the associated data, encrypts,
+/// then squeezes the tag, and `verify` checks it on the other side.
+#[derive(Clone)]
+pub struct Duplex {
+    inner: Sponge,
+    init: Sponge,
+    phase: Phase
+}
+
+impl Duplex {
+    #[inline]
+    pub fn new_duplex128(name: &[u8], custom: &[u8]) -> Self {
+        Duplex::new(CShake::new_cshake128(name, custom))
+    }
+
+    #[inline]
+    pub fn new_duplex256(name: &[u8], custom: &[u8]) -> Self {
+        Duplex::new(CShake::new_cshake256(name, custom))
+    }
+
+    #[inline]
+    fn new(cshake: CShake) -> Self {
+        let inner = cshake.into_sponge();
+        Duplex {
+            init: inner.clone(),
+            inner,
+            phase: Phase::Absorb
+        }
+    }
+
+    #[inline]
+    pub fn absorb(&mut self, buf: &[u8]) {
+        self.begin(Phase::Absorb);
+        self.inner.update(buf);
+    }
+
+    #[inline]
+    pub fn squeeze(&mut self, buf: &mut [u8]) {
+        self.begin(Phase::Squeeze);
+        self.inner.squeeze(buf);
+    }
+
+    /// Encrypts `buf` in place.
+    #[inline]
+    pub fn encrypt(&mut self, buf: &mut [u8]) {
+        self.begin(Phase::Crypt);
+        self.inner.crypt(buf, false);
+    }
+
+    /// Decrypts `buf` in place.
+    #[inline]
+    pub fn decrypt(&mut self, buf: &mut [u8]) {
+        self.begin(Phase::Crypt);
+        self.inner.crypt(buf, true);
+    }
+
+    /// Squeezes `tag.len()` bytes and compares them with `tag` in constant time.
+    ///
+    /// Tags shorter than `utils::MIN_TAG_LEN` never match, and are not squeezed.
+    #[inline]
+    pub fn verify(&mut self, tag: &[u8]) -> bool {
+        verify_tag(tag, |buf| self.squeeze(buf))
+    }
+
+    /// Ends the current phase and zeroes the rate, for forward secrecy.
+    ///
+    /// Whoever learns a later state cannot recover an earlier one, nor what was squeezed or
+    /// decrypted before. A new absorb phase starts.
+    #[inline]
+    pub fn ratchet(&mut self) {
+        self.end();
+        self.inner.zero_rate();
+        self.phase = Phase::Absorb;
+    }
+
+    /// Back to the state after `N` and `S`, forgetting every phase.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.inner = self.init.clone();
+        self.phase = Phase::Absorb;
+    }
+
+    #[inline]
+    fn begin(&mut self, phase: Phase) {
+        if self.phase != phase {
+            self.end();
+            self.phase = phase;
+        }
+    }
+
+    #[inline]
+    fn end(&mut self) {
+        match self.phase {
+            Phase::Absorb => self.inner.pad(ABSORB),
+            Phase::Crypt => self.inner.pad(CRYPT),
+            Phase::Squeeze => self.inner.fill_block()
+        }
+    }
+}
@@ -0,0 +1,178 @@
+/// Canonical encoding of a `TupleHash` element.
+///
+/// Every encoding starts with a one-byte type tag followed by the payload, so that values of
+/// different types (`1u8` and `1u16`, `"a"` and `b"a"`) do not share an encoding. Integers are
+/// fixed width and big-endian. The exception is `usize` and `isize`, which are encoded exactly as
+/// `u64` and `i64`, tag included, so the digest does not depend on the platform: `1usize` and
+/// `1u64` are the same element.
+///
+/// | type             | tag    | payload                                         |
+/// |------------------|--------|-------------------------------------------------|
+/// | `bool`           | `0x01` | `0x00` or `0x01`                                |
+/// | `u8` .. `u128`   | `0x02` .. `0x06` | big-endian                            |
+/// | `i8` .. `i128`   | `0x12` .. `0x16` | big-endian two's complement           |
+/// | `usize`, `isize` | `0x05`, `0x15` | as `u64` and `i64`                      |
+/// | `str`            | `0x20` | UTF-8 bytes                                     |
+/// | `[u8]`           | `0x21` | bytes                                           |
+/// | `Option<T>`      | `0x30` | `0x00`, or `0x01` followed by the encoding of `T` |
+/// | `SystemTime`     | `0x40` | seconds since the Unix epoch as `i64`, then nanoseconds as `u32` |
+pub trait TupleElement {
+    /// Length of the encoding in bytes.
+    fn encoded_len(&self) -> usize;
+
+    /// Feeds the encoding to `f`, possibly in several pieces.
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F);
+}
+
+const TAG_BOOL: u8 = 0x01;
+const TAG_STR: u8 = 0x20;
+const TAG_BYTES: u8 = 0x21;
+const TAG_OPTION: u8 = 0x30;
+#[cfg(feature = "std")] const TAG_SYSTEMTIME: u8 = 0x40;
+
+macro_rules! impl_integer {
+    ( $( $t:ty => $tag:expr ),* ) => {
+        $(
+            impl TupleElement for $t {
+                #[inline]
+                fn encoded_len(&self) -> usize {
+                    1 + self.to_be_bytes().len()
+                }
+
+                #[inline]
+                fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+                    f(&[$tag]);
+                    f(&self.to_be_bytes());
+                }
+            }
+        )*
+    }
+}
+
+impl_integer!(
+    u8 => 0x02, u16 => 0x03, u32 => 0x04, u64 => 0x05, u128 => 0x06,
+    i8 => 0x12, i16 => 0x13, i32 => 0x14, i64 => 0x15, i128 => 0x16
+);
+
+impl TupleElement for usize {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (*self as u64).encoded_len()
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        (*self as u64).encode(f)
+    }
+}
+
+impl TupleElement for isize {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (*self as i64).encoded_len()
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        (*self as i64).encode(f)
+    }
+}
+
+impl TupleElement for bool {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        2
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        f(&[TAG_BOOL, *self as u8]);
+    }
+}
+
+impl TupleElement for str {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1 + self.len()
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        f(&[TAG_STR]);
+        f(self.as_bytes());
+    }
+}
+
+impl TupleElement for [u8] {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1 + self.len()
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        f(&[TAG_BYTES]);
+        f(self);
+    }
+}
+
+impl<T: TupleElement> TupleElement for Option<T> {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        match *self {
+            Some(ref value) => 2 + value.encoded_len(),
+            None => 2
+        }
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        match *self {
+            Some(ref value) => {
+                f(&[TAG_OPTION, 0x01]);
+                value.encode(f);
+            },
+            None => f(&[TAG_OPTION, 0x00])
+        }
+    }
+}
+
+impl<T: TupleElement + ?Sized> TupleElement for &T {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        (**self).encoded_len()
+    }
+
+    #[inline]
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        (**self).encode(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl TupleElement for ::std::time::SystemTime {
+    #[inline]
+    fn encoded_len(&self) -> usize {
+        1 + 8 + 4
+    }
+
+    fn encode<F: FnMut(&[u8])>(&self, f: &mut F) {
+        use std::time::UNIX_EPOCH;
+
+        // floor the seconds so that the nanoseconds are always in `0..1_000_000_000`
+        let (secs, nanos) = match self.duration_since(UNIX_EPOCH) {
+            Ok(d) => (d.as_secs() as i64, d.subsec_nanos()),
+            Err(err) => {
+                let d = err.duration();
+                match d.subsec_nanos() {
+                    0 => (-(d.as_secs() as i64), 0),
+                    n => (-(d.as_secs() as i64) - 1, 1_000_000_000 - n)
+                }
+            }
+        };
+
+        f(&[TAG_SYSTEMTIME]);
+        f(&secs.to_be_bytes());
+        f(&nanos.to_be_bytes());
+    }
+}
@@ -0,0 +1,177 @@
+use core::sync::atomic::{ AtomicUsize, Ordering };
+use alloc::sync::Arc;
+use ::lanes;
+
+
+/// A batch of `ParallelHash` leaves and the buffer for their digests.
+///
+/// A batch can be split into smaller batches, and each of them hashed on any thread. Hashing
+/// consumes a batch, so no leaf is hashed twice, and the leaves hashed are counted, so that
+/// `ParallelHash` can tell when one was dropped instead.
+pub struct Leaves<'a> {
+    rate: usize,
+    blocksize: usize,
+    input: &'a [u8],
+    digests: &'a mut [u8],
+    hashed: &'a AtomicUsize
+}
+
+impl<'a> Leaves<'a> {
+    /// Leaves hashed together by one core, batches should be split on multiples of it.
+    pub const LANES: usize = lanes::MAX_LANES;
+
+    #[inline]
+    pub(crate) fn new(
+        rate: usize,
+        blocksize: usize,
+        input: &'a [u8],
+        digests: &'a mut [u8],
+        hashed: &'a AtomicUsize
+    ) -> Self {
+        Leaves { rate, blocksize, input, digests, hashed }
+    }
+
+    /// Number of leaves.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.input.len() / self.blocksize
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.input.is_empty()
+    }
+
+    /// Splits into the first `mid` leaves and the rest.
+    pub fn split_at(self, mid: usize) -> (Leaves<'a>, Leaves<'a>) {
+        let Leaves { rate, blocksize, input, digests, hashed } = self;
+        let (input0, input1) = input.split_at(mid * blocksize);
+        let (digests0, digests1) = digests.split_at_mut(mid * (200 - rate));
+
+        (
+            Leaves { rate, blocksize, input: input0, digests: digests0, hashed },
+            Leaves { rate, blocksize, input: input1, digests: digests1, hashed }
+        )
+    }
+
+    /// Splits into batches of `size` leaves, the last one may be shorter.
+    pub fn chunks(self, size: usize) -> impl Iterator<Item = Leaves<'a>> {
+        let Leaves { rate, blocksize, input, digests, hashed } = self;
+        input.chunks(size * blocksize)
+            .zip(digests.chunks_mut(size * (200 - rate)))
+            .map(move |(input, digests)| Leaves { rate, blocksize, input, digests, hashed })
+    }
+
+    /// Hashes the leaves on the current thread.
+    #[inline]
+    pub fn hash(self) {
+        lanes::hash_leaves(self.rate, self.blocksize, self.input, self.digests);
+        self.hashed.fetch_add(self.len(), Ordering::Relaxed);
+    }
+}
+
+
+/// Runs the leaf hashing of `ParallelHash`.
+///
+/// An executor must hash every leaf of the batch, by calling `Leaves::hash` on it or on the parts
+/// it splits it into, before `execute` returns. `ParallelHash` panics if a leaf was not hashed.
+pub trait Executor: Send + Sync {
+    fn execute(&self, leaves: Leaves);
+}
+
+impl<E: Executor + ?Sized> Executor for Arc<E> {
+    #[inline]
+    fn execute(&self, leaves: Leaves) {
+        (**self).execute(leaves)
+    }
+}
+
+/// Runs on the pool, as with `install`.
+#[cfg(feature = "rayon")]
+impl Executor for ::rayon::ThreadPool {
+    #[inline]
+    fn execute(&self, leaves: Leaves) {
+        self.install(|| par_hash(leaves))
+    }
+}
+
+
+/// The built-in executors.
+///
+/// All backends produce the same output.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Backend {
+    /// One leaf after another on the calling thread, needs only `alloc`.
+    Sequential,
+
+    /// Scoped `std::thread`s, one per available core.
+    #[cfg(feature = "std")]
+    Threads,
+
+    /// The global rayon pool.
+    #[cfg(feature = "rayon")]
+    Rayon
+}
+
+impl Default for Backend {
+    #[cfg(feature = "rayon")]
+    #[inline]
+    fn default() -> Self {
+        Backend::Rayon
+    }
+
+    #[cfg(all(feature = "std", not(feature = "rayon")))]
+    #[inline]
+    fn default() -> Self {
+        Backend::Threads
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    fn default() -> Self {
+        Backend::Sequential
+    }
+}
+
+impl Executor for Backend {
+    fn execute(&self, leaves: Leaves) {
+        match *self {
+            Backend::Sequential => leaves.hash(),
+            #[cfg(feature = "std")]
+            Backend::Threads => {
+                use std::thread;
+
+                let count = leaves.len();
+                let threads = thread::available_parallelism()
+                    .map(|n| n.get())
+                    .unwrap_or(1)
+                    .min(count);
+
+                if threads <= 1 {
+                    return leaves.hash();
+                }
+
+                // whole groups of lanes per thread
+                let per_thread = count.div_ceil(threads).next_multiple_of(Leaves::LANES);
+                thread::scope(|s| {
+                    for leaves in leaves.chunks(per_thread) {
+                        s.spawn(move || leaves.hash());
+                    }
+                });
+            },
+            #[cfg(feature = "rayon")]
+            Backend::Rayon => par_hash(leaves)
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+fn par_hash(leaves: Leaves) {
+    use rayon::prelude::*;
+
+    let Leaves { rate, blocksize, input, digests, hashed } = leaves;
+    input.par_chunks(Leaves::LANES * blocksize)
+        .zip(digests.par_chunks_mut(Leaves::LANES * (200 - rate)))
+        .for_each(|(input, digests)| lanes::hash_leaves(rate, blocksize, input, digests));
+    hashed.fetch_add(input.len() / blocksize, Ordering::Relaxed);
+}
@@ -0,0 +1,73 @@
+use ::sponge::XofStream;
+use ::cshake::CShake;
+use ::lanes;
+use ::parallelcore::ParallelCore;
+
+
+/// `ParallelHash` with the block size `B` fixed at compile time, without an allocator.
+///
+/// The output is the same as `ParallelHash` with `blocksize = B`. The unfinished leaf is kept as
+/// a sponge, and full leaves are hashed a few at a time into a buffer on the stack.
+///
+/// A zero block size is rejected at compile time.
+#[derive(Clone)]
+pub struct FixedParallelHash<const B: usize> {
+    core: ParallelCore
+}
+
+impl<const B: usize> FixedParallelHash<B> {
+    const VALID_BLOCKSIZE: () = assert!(B > 0, "ParallelHash block size must be positive");
+
+    #[inline]
+    pub fn new_parallelhash128(custom: &[u8]) -> Self {
+        FixedParallelHash::new(CShake::new_cshake128(b"ParallelHash", custom), 128)
+    }
+
+    #[inline]
+    pub fn new_parallelhash256(custom: &[u8]) -> Self {
+        FixedParallelHash::new(CShake::new_cshake256(b"ParallelHash", custom), 256)
+    }
+
+    fn new(inner: CShake, rate: usize) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::VALID_BLOCKSIZE;
+
+        FixedParallelHash { core: ParallelCore::new(inner, rate, B) }
+    }
+
+    pub fn update(&mut self, buf: &[u8]) {
+        self.core.update(B, buf, |core, leaves, _| {
+            let dlen = core.rate / 4;
+            let mut digests = [0; lanes::MAX_LANES * 64];
+            for group in leaves.chunks(lanes::MAX_LANES * B) {
+                let digests = &mut digests[..group.len() / B * dlen];
+                lanes::hash_leaves(200 - dlen, B, group, digests);
+                core.absorb_digests(digests);
+            }
+            leaves.len()
+        });
+    }
+
+    #[inline]
+    pub fn finalize(self, buf: &mut [u8]) {
+        self.core.finalize(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.core.finalize_reset(buf)
+    }
+
+    /// Back to the state after `left_encode(B)`.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.core.reset()
+    }
+
+    /// `ParallelHashXOF`, see `ParallelHash::xof`.
+    #[inline]
+    pub fn xof(self) -> XofStream {
+        self.core.xof()
+    }
+}
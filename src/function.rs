@@ -0,0 +1,288 @@
+//! User-defined functions on `cSHAKE`.
+//!
+//! SP800-185 reserves the function name `N` for functions defined by NIST, and this crate uses a
+//! few more. `FunctionBuilder` defines a new function with its own `N` and customization string
+//! `S`, refusing the reserved names and the empty name unless asked, and `Registry` catches two
+//! functions of an application built with the same rate, `N` and `S`.
+
+use core::fmt;
+use ::cshake::CShake;
+use ::sponge::XofStream;
+use ::utils::right_encode;
+#[cfg(feature = "alloc")] use alloc::collections::BTreeSet;
+#[cfg(feature = "alloc")] use alloc::vec::Vec;
+
+
+/// Function names of SP800-185, and of this crate.
+pub const RESERVED_NAMES: &[&[u8]] = &[
+    b"KMAC",
+    b"TupleHash",
+    b"ParallelHash",
+    b"HashId",
+    b"HashId checksum",
+    b"Transcript",
+    b"Midstate"
+];
+
+/// Whether `FunctionBuilder` refuses `name` without `allow_reserved`.
+///
+/// The empty name is `cSHAKE` itself, and `SHAKE` with an empty customization string.
+#[inline]
+pub fn is_reserved(name: &[u8]) -> bool {
+    name.is_empty() || RESERVED_NAMES.contains(&name)
+}
+
+
+/// How the output length is treated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Output {
+    /// As `cSHAKE`, a shorter output is a prefix of a longer one.
+    Xof,
+    /// As `KMAC`, `right_encode(L)` is absorbed before the output, `right_encode(0)` for `xof`,
+    /// so that outputs of different lengths are unrelated.
+    BoundLength
+}
+
+/// How the input of each `update` is absorbed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Encoding {
+    /// As `cSHAKE`, the updates are concatenated.
+    Raw,
+    /// As `TupleHash`, each update is one string `encode_string(X[i])`.
+    Strings
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BuildError {
+    /// The function name is reserved or empty, see `FunctionBuilder::allow_reserved`.
+    ReservedName,
+    /// A function with the same rate, name and customization string is already registered.
+    Collision
+}
+
+impl fmt::Display for BuildError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            BuildError::ReservedName => f.write_str("reserved function name"),
+            BuildError::Collision => f.write_str("function rate, name and customization string already registered")
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for BuildError {}
+
+
+/// Definition of a function `cSHAKE(encode(X), L, N, S)`.
+///
+/// ```
+/// use sp800_185::function::{ FunctionBuilder, Output, Encoding };
+///
+/// let builder = FunctionBuilder::new_function256(b"MyKdf")
+///     .with_custom(b"v1")
+///     .with_output(Output::BoundLength)
+///     .with_encoding(Encoding::Strings);
+///
+/// let mut kdf = builder.build().unwrap();
+/// kdf.update(b"secret");
+/// kdf.update(b"context");
+/// let mut key = [0; 32];
+/// kdf.finalize(&mut key);
+///
+/// assert!(FunctionBuilder::new_function256(b"KMAC").build().is_err());
+/// ```
+#[derive(Debug, Clone)]
+pub struct FunctionBuilder<'a> {
+    name: &'a [u8],
+    custom: &'a [u8],
+    rate: usize,
+    output: Output,
+    encoding: Encoding,
+    allow_reserved: bool
+}
+
+impl<'a> FunctionBuilder<'a> {
+    /// A function on `cSHAKE128`, with `Output::Xof` and `Encoding::Raw`.
+    #[inline]
+    pub fn new_function128(name: &'a [u8]) -> Self {
+        FunctionBuilder::new(name, 168)
+    }
+
+    /// A function on `cSHAKE256`, with `Output::Xof` and `Encoding::Raw`.
+    #[inline]
+    pub fn new_function256(name: &'a [u8]) -> Self {
+        FunctionBuilder::new(name, 136)
+    }
+
+    fn new(name: &'a [u8], rate: usize) -> Self {
+        FunctionBuilder {
+            name,
+            custom: b"",
+            rate,
+            output: Output::Xof,
+            encoding: Encoding::Raw,
+            allow_reserved: false
+        }
+    }
+
+    #[inline]
+    pub fn with_custom(mut self, custom: &'a [u8]) -> Self {
+        self.custom = custom;
+        self
+    }
+
+    #[inline]
+    pub fn with_output(mut self, output: Output) -> Self {
+        self.output = output;
+        self
+    }
+
+    #[inline]
+    pub fn with_encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Accepts a name of `RESERVED_NAMES`, to reproduce or extend one of those functions, or the
+    /// empty name.
+    #[inline]
+    pub fn allow_reserved(mut self) -> Self {
+        self.allow_reserved = true;
+        self
+    }
+
+    #[inline]
+    pub fn name(&self) -> &'a [u8] {
+        self.name
+    }
+
+    #[inline]
+    pub fn custom(&self) -> &'a [u8] {
+        self.custom
+    }
+
+    pub fn build(&self) -> Result<Function, BuildError> {
+        self.check_name()?;
+
+        let inner = if self.rate == 168 {
+            CShake::new_cshake128(self.name, self.custom)
+        } else {
+            CShake::new_cshake256(self.name, self.custom)
+        };
+
+        Ok(Function {
+            inner,
+            output: self.output,
+            encoding: self.encoding
+        })
+    }
+
+    #[inline]
+    fn check_name(&self) -> Result<(), BuildError> {
+        if !self.allow_reserved && is_reserved(self.name) {
+            Err(BuildError::ReservedName)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+
+/// A function built by `FunctionBuilder`.
+#[derive(Clone)]
+pub struct Function {
+    inner: CShake,
+    output: Output,
+    encoding: Encoding
+}
+
+impl Function {
+    #[inline]
+    pub fn update(&mut self, buf: &[u8]) {
+        match self.encoding {
+            Encoding::Raw => self.inner.update(buf),
+            Encoding::Strings => self.inner.absorb_string("encode_string(X[i])", buf)
+        }
+    }
+
+    #[inline]
+    pub fn finalize(mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.inner.finalize(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.inner.finalize_reset(buf)
+    }
+
+    /// Back to the state after `N` and `S`.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.inner.reset()
+    }
+
+    #[inline]
+    pub fn xof(mut self) -> XofStream {
+        self.with_bitlength(0);
+        self.inner.xof()
+    }
+
+    #[inline]
+    fn with_bitlength(&mut self, bitlength: u64) {
+        if self.output == Output::BoundLength {
+            let mut encbuf = [0; 9];
+
+            let pos = right_encode(&mut encbuf, bitlength);
+            self.inner.absorb("right_encode(L)", &encbuf[pos..]);
+        }
+    }
+}
+
+
+/// The functions of an application, by rate, name and customization string.
+///
+/// Two functions with the same rate, `N` and `S` share a domain whatever their other settings,
+/// so registering the second is an error. Reserved names are refused as by `build`.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Default)]
+pub struct Registry {
+    functions: BTreeSet<(usize, Vec<u8>, Vec<u8>)>
+}
+
+#[cfg(feature = "alloc")]
+impl Registry {
+    #[inline]
+    pub fn new() -> Self {
+        Registry::default()
+    }
+
+    pub fn register(&mut self, builder: &FunctionBuilder) -> Result<(), BuildError> {
+        builder.check_name()?;
+
+        if self.functions.insert((builder.rate, builder.name.to_vec(), builder.custom.to_vec())) {
+            Ok(())
+        } else {
+            Err(BuildError::Collision)
+        }
+    }
+
+    /// Registers the function, then builds it.
+    pub fn build(&mut self, builder: &FunctionBuilder) -> Result<Function, BuildError> {
+        let function = builder.build()?;
+        self.register(builder)?;
+        Ok(function)
+    }
+
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.functions.len()
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.functions.is_empty()
+    }
+}
@@ -0,0 +1,143 @@
+//! RFC 9380 `expand_message_xof` and `hash_to_field`.
+//!
+//! The XOF `H` is `SHAKE128` or `SHAKE256` for the suites of the RFC, or a `cSHAKE` instance
+//! with its own customization string. `k` is the target security level of the suite in bits.
+
+use ::sponge::XofStream;
+use ::cshake::CShake;
+use ::shake::Shake;
+
+
+/// Largest modulus of `hash_to_field`, in bytes.
+pub const MAX_MODULUS_LEN: usize = 64;
+
+/// Largest security level `k`, so that an oversized DST is hashed to at most 255 bytes.
+pub const MAX_SECURITY: usize = 1020;
+
+
+/// An extendable-output function usable as `H`, in its initial state.
+pub trait Xof: Clone {
+    fn update(&mut self, buf: &[u8]);
+    fn xof(self) -> XofStream;
+}
+
+impl Xof for Shake {
+    #[inline]
+    fn update(&mut self, buf: &[u8]) {
+        Shake::update(self, buf)
+    }
+
+    #[inline]
+    fn xof(self) -> XofStream {
+        Shake::xof(self)
+    }
+}
+
+impl Xof for CShake {
+    #[inline]
+    fn update(&mut self, buf: &[u8]) {
+        CShake::update(self, buf)
+    }
+
+    #[inline]
+    fn xof(self) -> XofStream {
+        CShake::xof(self)
+    }
+}
+
+
+/// `expand_message_xof(msg, DST, len(out))`, section 5.3.2.
+///
+/// A DST longer than 255 bytes is replaced by `H("H2C-OVERSIZE-DST-" || DST, ceil(2k / 8))`,
+/// section 5.3.3. Panics if `out` is longer than 65535 bytes.
+pub fn expand_message_xof<H: Xof>(h: &H, msg: &[u8], dst: &[u8], k: usize, out: &mut [u8]) {
+    expand(h, msg, dst, k, out.len()).fill(out)
+}
+
+/// `hash_to_field(msg, count)`, section 5.2, with `m = 1` over the prime field of modulus `p`.
+///
+/// `p` is big-endian, and `out` holds `count` elements of `p.len()` bytes each, big-endian. The
+/// elements of an extension field of degree `m` are the `count * m` elements in order.
+pub fn hash_to_field<H: Xof>(h: &H, msg: &[u8], dst: &[u8], k: usize, p: &[u8], out: &mut [u8]) {
+    let plen = p.len();
+    let bits = p.iter()
+        .position(|&b| b != 0)
+        .map(|i| (plen - i) * 8 - p[i].leading_zeros() as usize)
+        .unwrap_or(0);
+    assert!(plen <= MAX_MODULUS_LEN, "modulus is too large");
+    assert!(bits >= 2, "modulus must be at least 2");
+    assert!(out.len().is_multiple_of(plen), "output must be a whole number of elements");
+
+    let l = (bits + k).div_ceil(8);
+    let count = out.len() / plen;
+    let mut stream = expand(h, msg, dst, k, count * l);
+
+    let mut tv = [0; MAX_MODULUS_LEN + MAX_SECURITY.div_ceil(8)];
+    for e in out.chunks_mut(plen) {
+        let tv = &mut tv[..l];
+        stream.fill(tv);
+        reduce(tv, p, e);
+    }
+}
+
+
+fn expand<H: Xof>(h: &H, msg: &[u8], dst: &[u8], k: usize, len: usize) -> XofStream {
+    assert!(k <= MAX_SECURITY, "security level is too large");
+    assert!(len <= 0xffff, "output is too long");
+
+    let mut long = [0; 255];
+    let dst = if dst.len() > 255 {
+        let long = &mut long[..(2 * k).div_ceil(8)];
+        let mut h = h.clone();
+        h.update(b"H2C-OVERSIZE-DST-");
+        h.update(dst);
+        h.xof().fill(long);
+        &long[..]
+    } else {
+        dst
+    };
+
+    let mut h = h.clone();
+    h.update(msg);
+    h.update(&(len as u16).to_be_bytes());
+    h.update(dst);
+    h.update(&[dst.len() as u8]);
+    h.xof()
+}
+
+/// `out = OS2IP(x) mod p`, one bit at a time, without branching on `x`.
+fn reduce(x: &[u8], p: &[u8], out: &mut [u8]) {
+    // r < p, with one more byte for the shift
+    let mut r = [0; MAX_MODULUS_LEN + 1];
+    let mut d = [0; MAX_MODULUS_LEN + 1];
+    let n = p.len() + 1;
+    let r = &mut r[..n];
+    let d = &mut d[..n];
+
+    for i in 0..x.len() * 8 {
+        let bit = (x[i / 8] >> (7 - i % 8)) & 1;
+
+        // r = 2r + bit
+        let mut carry = bit;
+        for b in r.iter_mut().rev() {
+            let next = *b >> 7;
+            *b = (*b << 1) | carry;
+            carry = next;
+        }
+
+        // d = r - p, keep it if it did not borrow
+        let mut borrow = 0u16;
+        for j in (0..n).rev() {
+            let pj = if j == 0 { 0 } else { u16::from(p[j - 1]) };
+            let diff = u16::from(r[j]).wrapping_sub(pj).wrapping_sub(borrow);
+            d[j] = diff as u8;
+            borrow = (diff >> 8) & 1;
+        }
+        let keep = (borrow as u8).wrapping_sub(1);
+        for (rj, &dj) in r.iter_mut().zip(d.iter()) {
+            *rj = (dj & keep) | (*rj & !keep);
+        }
+    }
+
+    out.copy_from_slice(&r[1..]);
+}
@@ -0,0 +1,214 @@
+//! Name-based identifiers.
+//!
+//! Stable identifiers derived from a namespace and a name, in the spirit of RFC 9562 UUIDv5,
+//! but with `cSHAKE128` instead of SHA-1. The namespace is the customization string, so
+//! identifiers from different namespaces are unrelated.
+
+use ::cshake::CShake;
+use ::utils::right_encode;
+
+
+/// Fixed-width identifier.
+///
+/// `cSHAKE128(X = name || right_encode(L), L, N = "HashId", S = namespace)`. As with `KMAC`, the
+/// output length is absorbed, so a 16-byte identifier is not a prefix of the 32-byte one.
+pub fn hash_id(namespace: &[u8], name: &[u8], buf: &mut [u8]) {
+    let mut encbuf = [0; 9];
+
+    let mut cshake = CShake::new_cshake128(b"HashId", namespace);
+    cshake.update(name);
+
+    let pos = right_encode(&mut encbuf, buf.len() as u64 * 8);
+    cshake.absorb("right_encode(L)", &encbuf[pos..]);
+
+    cshake.finalize(buf);
+}
+
+/// RFC 9562 UUIDv8.
+///
+/// The 128-bit `hash_id` with the version and variant bits overwritten, the way UUIDv5
+/// truncates SHA-1.
+pub fn uuid_v8(namespace: &[u8], name: &[u8]) -> [u8; 16] {
+    let mut uuid = [0; 16];
+    hash_id(namespace, name, &mut uuid);
+
+    uuid[6] = (uuid[6] & 0x0f) | 0x80; // version 8
+    uuid[8] = (uuid[8] & 0x3f) | 0x80; // variant 0b10
+    uuid
+}
+
+#[cfg(feature = "std")]
+pub use self::text::*;
+
+#[cfg(feature = "std")]
+mod text {
+    use std::fmt;
+    use std::error::Error;
+    use ::cshake::CShake;
+
+
+    const CHECKSUM_LEN: usize = 4;
+    const BASE32_ALPHABET: &[u8; 32] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ";
+    const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum DecodeError {
+        InvalidCharacter,
+        InvalidLength,
+        InvalidChecksum
+    }
+
+    impl fmt::Display for DecodeError {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            f.write_str(match *self {
+                DecodeError::InvalidCharacter => "invalid character",
+                DecodeError::InvalidLength => "invalid length",
+                DecodeError::InvalidChecksum => "invalid checksum"
+            })
+        }
+    }
+
+    impl Error for DecodeError {}
+
+    /// `cSHAKE128(id, 32, "HashId checksum", "")`.
+    ///
+    /// The function name is not `"HashId"`, so no namespace of `hash_id` gives the checksum.
+    fn checksum(id: &[u8]) -> [u8; CHECKSUM_LEN] {
+        let mut sum = [0; CHECKSUM_LEN];
+        let mut cshake = CShake::new_cshake128(b"HashId checksum", b"");
+        cshake.update(id);
+        cshake.finalize(&mut sum);
+        sum
+    }
+
+    fn with_checksum(id: &[u8]) -> Vec<u8> {
+        let mut buf = Vec::with_capacity(id.len() + CHECKSUM_LEN);
+        buf.extend_from_slice(id);
+        buf.extend_from_slice(&checksum(id));
+        buf
+    }
+
+    fn check_checksum(mut buf: Vec<u8>) -> Result<Vec<u8>, DecodeError> {
+        if buf.len() < CHECKSUM_LEN {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let pos = buf.len() - CHECKSUM_LEN;
+        if !::utils::ct_eq(&checksum(&buf[..pos]), &buf[pos..]) {
+            return Err(DecodeError::InvalidChecksum);
+        }
+
+        buf.truncate(pos);
+        Ok(buf)
+    }
+
+    /// Crockford base32 of `id || checksum`, without padding.
+    pub fn encode_base32(id: &[u8]) -> String {
+        let buf = with_checksum(id);
+        let mut output = String::with_capacity((buf.len() * 8).div_ceil(5));
+        let mut acc = 0u16;
+        let mut bits = 0;
+
+        for &b in &buf {
+            acc = (acc << 8) | u16::from(b);
+            bits += 8;
+            while bits >= 5 {
+                bits -= 5;
+                output.push(BASE32_ALPHABET[(acc >> bits) as usize & 0x1f] as char);
+            }
+        }
+        if bits > 0 {
+            output.push(BASE32_ALPHABET[(acc << (5 - bits)) as usize & 0x1f] as char);
+        }
+
+        output
+    }
+
+    /// Decodes `encode_base32`.
+    ///
+    /// Decoding is case-insensitive and accepts `O` for `0` and `I`, `L` for `1`.
+    pub fn decode_base32(input: &str) -> Result<Vec<u8>, DecodeError> {
+        if input.len() * 5 % 8 >= 5 {
+            return Err(DecodeError::InvalidLength);
+        }
+
+        let mut buf = Vec::with_capacity(input.len() * 5 / 8);
+        let mut acc = 0u16;
+        let mut bits = 0;
+
+        for c in input.bytes() {
+            let v = match c.to_ascii_uppercase() {
+                b'O' => 0,
+                b'I' | b'L' => 1,
+                c => BASE32_ALPHABET.iter()
+                    .position(|&a| a == c)
+                    .ok_or(DecodeError::InvalidCharacter)?
+            };
+            acc = (acc << 5) | v as u16;
+            bits += 5;
+            if bits >= 8 {
+                bits -= 8;
+                buf.push((acc >> bits) as u8);
+            }
+        }
+
+        // non-canonical trailing bits
+        if acc & ((1 << bits) - 1) != 0 {
+            return Err(DecodeError::InvalidCharacter);
+        }
+
+        check_checksum(buf)
+    }
+
+    /// Base58 (Bitcoin alphabet) of `id || checksum`.
+    pub fn encode_base58(id: &[u8]) -> String {
+        let buf = with_checksum(id);
+        let zeros = buf.iter().take_while(|&&b| b == 0).count();
+
+        // little-endian base 58 digits
+        let mut digits: Vec<u8> = Vec::with_capacity(buf.len() * 138 / 100 + 1);
+        for &b in &buf[zeros..] {
+            let mut carry = u32::from(b);
+            for d in &mut digits {
+                carry += u32::from(*d) << 8;
+                *d = (carry % 58) as u8;
+                carry /= 58;
+            }
+            while carry > 0 {
+                digits.push((carry % 58) as u8);
+                carry /= 58;
+            }
+        }
+
+        let mut output = String::with_capacity(zeros + digits.len());
+        output.extend((0..zeros).map(|_| BASE58_ALPHABET[0] as char));
+        output.extend(digits.iter().rev().map(|&d| BASE58_ALPHABET[d as usize] as char));
+        output
+    }
+
+    /// Decodes `encode_base58`.
+    pub fn decode_base58(input: &str) -> Result<Vec<u8>, DecodeError> {
+        let zeros = input.bytes().take_while(|&c| c == BASE58_ALPHABET[0]).count();
+
+        // little-endian base 256 digits
+        let mut bytes: Vec<u8> = Vec::with_capacity(input.len() * 733 / 1000 + 1);
+        for c in input.bytes().skip(zeros) {
+            let mut carry = BASE58_ALPHABET.iter()
+                .position(|&a| a == c)
+                .ok_or(DecodeError::InvalidCharacter)? as u32;
+            for b in &mut bytes {
+                carry += u32::from(*b) * 58;
+                *b = carry as u8;
+                carry >>= 8;
+            }
+            while carry > 0 {
+                bytes.push(carry as u8);
+                carry >>= 8;
+            }
+        }
+
+        let mut buf = vec![0; zeros];
+        buf.extend(bytes.iter().rev());
+        check_checksum(buf)
+    }
+}
@@ -0,0 +1,149 @@
+use core::{ cmp, mem };
+use ::sponge::{ Sponge, XofStream };
+use ::utils::right_encode;
+
+
+/// Chunk length of the tree.
+const CHUNK: usize = 8192;
+
+const SINGLE_NODE: u8 = 0x07;
+const FINAL_NODE: u8 = 0x06;
+const LEAF: u8 = 0x0b;
+
+
+/// KangarooTwelve, `KT128` and `KT256` of RFC 9861.
+///
+/// The input `S = M || C || length_encode(|C|)` is cut into chunks of 8192 bytes. A single chunk
+/// is hashed by TurboSHAKE with `D = 0x07`. Otherwise the first chunk is followed, in the final
+/// node, by `0x03 || 0x00^7`, the chaining values of the other chunks, hashed by TurboSHAKE
+/// with `D = 0x0b`, `length_encode(n)` and `0xff || 0xff`, and the final node is hashed with
+/// `D = 0x06`.
+///
+/// The customization string `C` is absorbed after the message, so it is borrowed until then.
+#[derive(Clone)]
+pub struct KangarooTwelve<'a> {
+    custom: &'a [u8],
+    node: Sponge,
+    leaf: Sponge,
+    leaf_len: usize,
+    len: u64,
+    n: u64,
+    rate: usize
+}
+
+impl<'a> KangarooTwelve<'a> {
+    #[inline]
+    pub fn new_kt128(custom: &'a [u8]) -> Self {
+        KangarooTwelve::new(custom, 168)
+    }
+
+    #[inline]
+    pub fn new_kt256(custom: &'a [u8]) -> Self {
+        KangarooTwelve::new(custom, 136)
+    }
+
+    fn new(custom: &'a [u8], rate: usize) -> Self {
+        KangarooTwelve {
+            custom,
+            node: Sponge::new_turbo(rate, SINGLE_NODE),
+            leaf: Sponge::new_turbo(rate, LEAF),
+            leaf_len: 0,
+            len: 0,
+            n: 0,
+            rate
+        }
+    }
+
+    #[inline]
+    pub fn update(&mut self, buf: &[u8]) {
+        self.absorb(buf)
+    }
+
+    #[inline]
+    pub fn finalize(self, buf: &mut [u8]) {
+        self.xof().fill(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        let fresh = KangarooTwelve::new(self.custom, self.rate);
+        mem::replace(self, fresh).finalize(buf)
+    }
+
+    /// Back to the state before any input, with the same customization string.
+    #[inline]
+    pub fn reset(&mut self) {
+        *self = KangarooTwelve::new(self.custom, self.rate);
+    }
+
+    pub fn xof(mut self) -> XofStream {
+        let mut encbuf = [0; 9];
+
+        let custom = self.custom;
+        self.absorb(custom);
+        let pos = length_encode(&mut encbuf, custom.len() as u64);
+        self.absorb(&encbuf[pos..]);
+
+        if self.len <= CHUNK as u64 {
+            return self.node.xof_with_delim(SINGLE_NODE);
+        }
+
+        if self.leaf_len > 0 {
+            self.finish_leaf();
+        }
+        let pos = length_encode(&mut encbuf, self.n);
+        self.node.update(&encbuf[pos..]);
+        self.node.update(&[0xff, 0xff]);
+        self.node.xof_with_delim(FINAL_NODE)
+    }
+
+    fn absorb(&mut self, mut buf: &[u8]) {
+        // the first chunk goes to the final node
+        if self.len < CHUNK as u64 {
+            let len = cmp::min(CHUNK - self.len as usize, buf.len());
+            self.node.update(&buf[..len]);
+            self.len += len as u64;
+            buf = &buf[len..];
+        }
+
+        while !buf.is_empty() {
+            if self.len == CHUNK as u64 {
+                self.node.update(&[0x03, 0, 0, 0, 0, 0, 0, 0]);
+            }
+
+            let len = cmp::min(CHUNK - self.leaf_len, buf.len());
+            self.leaf.update(&buf[..len]);
+            self.leaf_len += len;
+            self.len += len as u64;
+            buf = &buf[len..];
+
+            if self.leaf_len == CHUNK {
+                self.finish_leaf();
+            }
+        }
+    }
+
+    fn finish_leaf(&mut self) {
+        let mut cv = [0; 64];
+        let cv = &mut cv[..200 - self.rate];
+
+        let leaf = mem::replace(&mut self.leaf, Sponge::new_turbo(self.rate, LEAF));
+        leaf.finalize(cv);
+        self.node.update(cv);
+        self.leaf_len = 0;
+        self.n += 1;
+    }
+}
+
+
+/// `length_encode(x)`, as `right_encode` but with no byte for zero.
+#[inline]
+fn length_encode(buf: &mut [u8; 9], value: u64) -> usize {
+    if value == 0 {
+        buf[8] = 0;
+        8
+    } else {
+        right_encode(buf, value)
+    }
+}
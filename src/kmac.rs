@@ -1,6 +1,8 @@
-use tiny_keccak::XofReader;
+use ::sponge::XofStream;
 use ::cshake::CShake;
-use ::utils::{ left_encode, right_encode };
+use ::prefix::Prefix;
+use ::utils::{ left_encode, right_encode, verify_tag };
+use ::midstate::{ self, MidstateError, MIDSTATE_LEN, SEALED_LEN, KIND_KMAC };
 
 
 /// KECCAK Message Authentication Code.
@@ -30,18 +32,50 @@ impl KMac {
         kmac
     }
 
+    /// `KMAC` from `Prefix::new_kmac128` or `Prefix::new_kmac256`, and the key.
+    #[inline]
+    pub fn from_prefix(prefix: &Prefix, key: &[u8]) -> Self {
+        assert_eq!(prefix.name(), b"KMAC", "not a KMAC prefix");
+        let mut kmac = KMac(CShake::from_prefix(prefix));
+        let rate = kmac.0.rate();
+        kmac.init(key, rate);
+        kmac
+    }
+
     fn init(&mut self, key: &[u8], rate: usize) {
         let mut encbuf = [0; 9];
 
         // bytepad(encode_string(k))
         let pos = left_encode(&mut encbuf, rate as u64);
-        self.0.update(&encbuf[pos..]);
+        self.0.absorb("left_encode(rate)", &encbuf[pos..]);
+        self.0.absorb_string("encode_string(K)", key);
+
+        self.0.fill_block();
+        self.0.set_init();
+    }
+
+    /// The state sealed with `key`, to resume with `import_sealed`.
+    ///
+    /// The state holds the `KMAC` key in all but name, and is encrypted and authenticated. The
+    /// same state sealed twice with the same key gives the same bytes.
+    pub fn export_sealed(&self, key: &[u8]) -> [u8; SEALED_LEN] {
+        midstate::seal(key, &self.0.export_kind(KIND_KMAC))
+    }
+
+    pub fn import_sealed(key: &[u8], buf: &[u8]) -> Result<Self, MidstateError> {
+        let midstate = midstate::open(key, buf, KIND_KMAC)?;
+        KMac::dangerous_import_midstate(&midstate)
+    }
 
-        let pos = left_encode(&mut encbuf, key.len() as u64 * 8);
-        self.0.update(&encbuf[pos..]);
-        self.0.update(key);
+    /// The state in the clear, which allows computing tags as if with the `KMAC` key.
+    #[inline]
+    pub fn dangerous_export_midstate(&self) -> [u8; MIDSTATE_LEN] {
+        self.0.export_kind(KIND_KMAC)
+    }
 
-        (self.0).0.fill_block();
+    #[inline]
+    pub fn dangerous_import_midstate(buf: &[u8]) -> Result<Self, MidstateError> {
+        CShake::import_kind(buf, KIND_KMAC).map(KMac)
     }
 
     #[inline]
@@ -49,19 +83,62 @@ impl KMac {
         self.0.update(buf)
     }
 
+    /// Keyed `TupleHash`.
+    ///
+    /// Absorbs each element as `encode_string(X[i])`, the same framing `TupleHash` uses, so that
+    /// the tuple ("abc", "d") and the tuple ("ab", "cd") produce unrelated tags.
+    pub fn update_tuple<T: AsRef<[u8]>>(&mut self, input: &[T]) {
+        for buf in input {
+            self.0.absorb_string("encode_string(X[i])", buf.as_ref());
+        }
+    }
+
     #[inline]
     pub fn finalize(mut self, buf: &mut [u8]) {
         self.with_bitlength(buf.len() as u64 * 8);
         self.0.finalize(buf);
     }
 
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.0.finalize_reset(buf);
+    }
+
+    /// Back to the state after the key, which is not needed again.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    /// Compares the tag in constant time.
+    ///
+    /// The output length `L` is taken from `tag`, so a truncated tag is not a prefix of a longer one.
+    /// Tags shorter than `utils::MIN_TAG_LEN` are rejected.
+    #[inline]
+    pub fn verify(mut self, tag: &[u8]) -> bool {
+        self.with_bitlength(tag.len() as u64 * 8);
+        let mut reader = self.0.xof();
+        verify_tag(tag, |buf| reader.squeeze(buf))
+    }
+
+    /// Compares the prefix of the XOF output in constant time.
+    ///
+    /// Tags shorter than `utils::MIN_TAG_LEN` are rejected.
+    #[inline]
+    pub fn verify_xof(self, tag: &[u8]) -> bool {
+        let mut reader = self.xof();
+        verify_tag(tag, |buf| reader.squeeze(buf))
+    }
+
     /// A function on bit strings in which the output can be extended to  any desired length.
     ///
     /// Some applications of `KMAC` may not know the number of output bits they will need until after
     /// the outputs begin to be produced. For these applications, `KMAC` can also be used as a XOF (i.e.,
     /// the output can be extended to any desired length), which mimics the behavior of `cSHAKE`.
     #[inline]
-    pub fn xof(mut self) -> XofReader {
+    pub fn xof(mut self) -> XofStream {
         self.with_bitlength(0);
         self.0.xof()
     }
@@ -70,8 +147,7 @@ impl KMac {
     fn with_bitlength(&mut self, bitlength: u64) {
         let mut encbuf = [0; 9];
 
-        // right_encode(L)
         let pos = right_encode(&mut encbuf, bitlength);
-        self.0.update(&encbuf[pos..]);
+        self.0.absorb("right_encode(L)", &encbuf[pos..]);
     }
 }
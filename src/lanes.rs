@@ -0,0 +1,487 @@
+//! Multi-lane `SHAKE` for `ParallelHash` leaves.
+//!
+//! The leaves of `ParallelHash` are independent `SHAKE` computations of the same length, so
+//! several of them can share one interleaved `Keccak-f[1600]` state, one vector per state word,
+//! and be permuted together: 8 lanes with AVX-512, 4 with AVX2, 2 with SSE2, and 2 plain `u64`s
+//! on other targets. The instruction set is detected at runtime with `std`, at compile time
+//! without. A single `u64` is the built-in scalar permutation.
+
+use ::sponge::Sponge;
+#[cfg(test)] use tiny_keccak::Keccak;
+
+
+pub(crate) const RC: [u64; 24] = [
+    0x0000000000000001, 0x0000000000008082, 0x800000000000808a, 0x8000000080008000,
+    0x000000000000808b, 0x0000000080000001, 0x8000000080008081, 0x8000000000008009,
+    0x000000000000008a, 0x0000000000000088, 0x0000000080008009, 0x000000008000000a,
+    0x000000008000808b, 0x800000000000008b, 0x8000000000008089, 0x8000000000008003,
+    0x8000000000008002, 0x8000000000000080, 0x000000000000800a, 0x800000008000000a,
+    0x8000000080008081, 0x8000000000008080, 0x0000000080000001, 0x8000000080008008
+];
+
+
+/// `N` words, one from each interleaved state.
+///
+/// # Safety
+///
+/// The methods may only be called where the instruction set of the implementation is available.
+unsafe trait Lanes: Copy {
+    const N: usize;
+
+    fn splat(x: u64) -> Self;
+    /// Loads `words[..N]`.
+    fn load(words: &[u64]) -> Self;
+    /// Stores into `words[..N]`.
+    fn store(self, words: &mut [u64]);
+    fn xor(self, b: Self) -> Self;
+    /// `!self & b`
+    fn andnot(self, b: Self) -> Self;
+    fn rotl(self, n: u32) -> Self;
+}
+
+unsafe impl Lanes for u64 {
+    const N: usize = 1;
+
+    #[inline(always)]
+    fn splat(x: u64) -> Self {
+        x
+    }
+
+    #[inline(always)]
+    fn load(words: &[u64]) -> Self {
+        words[0]
+    }
+
+    #[inline(always)]
+    fn store(self, words: &mut [u64]) {
+        words[0] = self;
+    }
+
+    #[inline(always)]
+    fn xor(self, b: Self) -> Self {
+        self ^ b
+    }
+
+    #[inline(always)]
+    fn andnot(self, b: Self) -> Self {
+        !self & b
+    }
+
+    #[inline(always)]
+    fn rotl(self, n: u32) -> Self {
+        self.rotate_left(n)
+    }
+}
+
+unsafe impl Lanes for [u64; 2] {
+    const N: usize = 2;
+
+    #[inline(always)]
+    fn splat(x: u64) -> Self {
+        [x; 2]
+    }
+
+    #[inline(always)]
+    fn load(words: &[u64]) -> Self {
+        [words[0], words[1]]
+    }
+
+    #[inline(always)]
+    fn store(self, words: &mut [u64]) {
+        words[..2].copy_from_slice(&self);
+    }
+
+    #[inline(always)]
+    fn xor(self, b: Self) -> Self {
+        [self[0] ^ b[0], self[1] ^ b[1]]
+    }
+
+    #[inline(always)]
+    fn andnot(self, b: Self) -> Self {
+        [!self[0] & b[0], !self[1] & b[1]]
+    }
+
+    #[inline(always)]
+    fn rotl(self, n: u32) -> Self {
+        [self[0].rotate_left(n), self[1].rotate_left(n)]
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+mod x86 {
+    use core::arch::x86_64::*;
+    use super::Lanes;
+
+    // the shift counts are constants once `keccakf` is inlined, and fold into immediates
+
+    unsafe impl Lanes for __m128i {
+        const N: usize = 2;
+
+        #[inline(always)]
+        fn splat(x: u64) -> Self {
+            unsafe { _mm_set1_epi64x(x as i64) }
+        }
+
+        #[inline(always)]
+        fn load(words: &[u64]) -> Self {
+            assert!(words.len() >= 2);
+            unsafe { _mm_loadu_si128(words.as_ptr() as *const _) }
+        }
+
+        #[inline(always)]
+        fn store(self, words: &mut [u64]) {
+            assert!(words.len() >= 2);
+            unsafe { _mm_storeu_si128(words.as_mut_ptr() as *mut _, self) }
+        }
+
+        #[inline(always)]
+        fn xor(self, b: Self) -> Self {
+            unsafe { _mm_xor_si128(self, b) }
+        }
+
+        #[inline(always)]
+        fn andnot(self, b: Self) -> Self {
+            unsafe { _mm_andnot_si128(self, b) }
+        }
+
+        #[inline(always)]
+        fn rotl(self, n: u32) -> Self {
+            unsafe {
+                _mm_or_si128(
+                    _mm_sll_epi64(self, _mm_cvtsi32_si128(n as i32)),
+                    _mm_srl_epi64(self, _mm_cvtsi32_si128(64 - n as i32))
+                )
+            }
+        }
+    }
+
+    unsafe impl Lanes for __m256i {
+        const N: usize = 4;
+
+        #[inline(always)]
+        fn splat(x: u64) -> Self {
+            unsafe { _mm256_set1_epi64x(x as i64) }
+        }
+
+        #[inline(always)]
+        fn load(words: &[u64]) -> Self {
+            assert!(words.len() >= 4);
+            unsafe { _mm256_loadu_si256(words.as_ptr() as *const _) }
+        }
+
+        #[inline(always)]
+        fn store(self, words: &mut [u64]) {
+            assert!(words.len() >= 4);
+            unsafe { _mm256_storeu_si256(words.as_mut_ptr() as *mut _, self) }
+        }
+
+        #[inline(always)]
+        fn xor(self, b: Self) -> Self {
+            unsafe { _mm256_xor_si256(self, b) }
+        }
+
+        #[inline(always)]
+        fn andnot(self, b: Self) -> Self {
+            unsafe { _mm256_andnot_si256(self, b) }
+        }
+
+        #[inline(always)]
+        fn rotl(self, n: u32) -> Self {
+            unsafe {
+                _mm256_or_si256(
+                    _mm256_sll_epi64(self, _mm_cvtsi32_si128(n as i32)),
+                    _mm256_srl_epi64(self, _mm_cvtsi32_si128(64 - n as i32))
+                )
+            }
+        }
+    }
+
+    unsafe impl Lanes for __m512i {
+        const N: usize = 8;
+
+        #[inline(always)]
+        fn splat(x: u64) -> Self {
+            unsafe { _mm512_set1_epi64(x as i64) }
+        }
+
+        #[inline(always)]
+        fn load(words: &[u64]) -> Self {
+            assert!(words.len() >= 8);
+            unsafe { _mm512_loadu_si512(words.as_ptr() as *const _) }
+        }
+
+        #[inline(always)]
+        fn store(self, words: &mut [u64]) {
+            assert!(words.len() >= 8);
+            unsafe { _mm512_storeu_si512(words.as_mut_ptr() as *mut _, self) }
+        }
+
+        #[inline(always)]
+        fn xor(self, b: Self) -> Self {
+            unsafe { _mm512_xor_si512(self, b) }
+        }
+
+        #[inline(always)]
+        fn andnot(self, b: Self) -> Self {
+            unsafe { _mm512_andnot_si512(self, b) }
+        }
+
+        #[inline(always)]
+        fn rotl(self, n: u32) -> Self {
+            unsafe { _mm512_rolv_epi64(self, _mm512_set1_epi64(n as i64)) }
+        }
+    }
+}
+
+
+/// Rho and pi, unrolled so that every rotation is by a constant.
+macro_rules! rho_pi {
+    ( $a:ident, $last:ident; $( $pi:expr, $rho:expr );* ) => {
+        $(
+            let tmp = $a[$pi];
+            $a[$pi] = $last.rotl($rho);
+            #[allow(unused_assignments)]
+            { $last = tmp; }
+        )*
+    }
+}
+
+/// `Keccak-f[1600]` on `W::N` interleaved states.
+#[inline(always)]
+unsafe fn keccakf<W: Lanes>(a: &mut [W; 25]) {
+    keccakp(a, 24)
+}
+
+/// `Keccak-p[1600]`, the last `rounds` rounds of `Keccak-f[1600]`.
+#[inline(always)]
+unsafe fn keccakp<W: Lanes>(a: &mut [W; 25], rounds: usize) {
+    for &rc in &RC[24 - rounds..] {
+        // Theta
+        let mut c = [a[0]; 5];
+        for x in 0..5 {
+            c[x] = a[x].xor(a[x + 5]).xor(a[x + 10]).xor(a[x + 15]).xor(a[x + 20]);
+        }
+        for x in 0..5 {
+            let d = c[(x + 4) % 5].xor(c[(x + 1) % 5].rotl(1));
+            for y in 0..5 {
+                a[y * 5 + x] = a[y * 5 + x].xor(d);
+            }
+        }
+
+        // Rho and pi
+        let mut last = a[1];
+        rho_pi!(a, last;
+            10, 1; 7, 3; 11, 6; 17, 10; 18, 15; 3, 21; 5, 28; 16, 36;
+            8, 45; 21, 55; 24, 2; 4, 14; 15, 27; 23, 41; 19, 56; 13, 8;
+            12, 25; 2, 43; 20, 62; 14, 18; 22, 39; 9, 61; 6, 20; 1, 44
+        );
+
+        // Chi
+        for y in 0..5 {
+            let row = [a[y * 5], a[y * 5 + 1], a[y * 5 + 2], a[y * 5 + 3], a[y * 5 + 4]];
+            for x in 0..5 {
+                a[y * 5 + x] = row[x].xor(row[(x + 1) % 5].andnot(row[(x + 2) % 5]));
+            }
+        }
+
+        // Iota
+        a[0] = a[0].xor(W::splat(rc));
+    }
+}
+
+/// XORs the first `words` words of `W::N` blocks, `stride` bytes apart, into `a`.
+#[inline(always)]
+unsafe fn xorin<W: Lanes>(a: &mut [W; 25], block: &[u8], stride: usize, words: usize) {
+    let mut w = [0; 8];
+    for (i, a) in a[..words].iter_mut().enumerate() {
+        for (l, w) in w[..W::N].iter_mut().enumerate() {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(&block[l * stride + i * 8..][..8]);
+            *w = u64::from_le_bytes(buf);
+        }
+        *a = a.xor(W::load(&w));
+    }
+}
+
+/// `SHAKE` of `W::N` leaves of `blocksize` bytes at once.
+#[inline(always)]
+unsafe fn leaves<W: Lanes>(rate: usize, blocksize: usize, leaves: &[u8], buf: &mut [u8]) {
+    let dlen = 200 - rate;
+    let mut a = [W::splat(0); 25];
+
+    let full = blocksize / rate;
+    for b in 0..full {
+        xorin(&mut a, &leaves[b * rate..], blocksize, rate / 8);
+        keccakf(&mut a);
+    }
+
+    // pad10*1 with the SHAKE suffix
+    let rem = blocksize % rate;
+    let mut block = [0; 8 * 168];
+    for l in 0..W::N {
+        let block = &mut block[l * rate..][..rate];
+        block[..rem].copy_from_slice(&leaves[l * blocksize + full * rate..][..rem]);
+        block[rem] ^= 0x1f;
+        block[rate - 1] ^= 0x80;
+    }
+    xorin(&mut a, &block, rate, rate / 8);
+    keccakf(&mut a);
+
+    let mut w = [0; 8];
+    for (i, a) in a[..dlen / 8].iter().enumerate() {
+        a.store(&mut w);
+        for (l, &w) in w[..W::N].iter().enumerate() {
+            buf[l * dlen + i * 8..][..8].copy_from_slice(&w.to_le_bytes());
+        }
+    }
+}
+
+#[inline(always)]
+unsafe fn hash_leaves_n<W: Lanes>(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+    let dlen = 200 - rate;
+
+    let mut groups = input.chunks_exact(W::N * blocksize);
+    let mut outputs = buf.chunks_exact_mut(W::N * dlen);
+    for (group, output) in (&mut groups).zip(&mut outputs) {
+        leaves::<W>(rate, blocksize, group, output);
+    }
+
+    for (chunk, output) in groups.remainder().chunks(blocksize)
+        .zip(outputs.into_remainder().chunks_mut(dlen))
+    {
+        let mut shake = Sponge::new(rate, 0x1f);
+        shake.update(chunk);
+        shake.finalize(output);
+    }
+}
+
+/// The built-in scalar `Keccak-p[1600]`.
+#[inline]
+pub(crate) fn keccakp1600(a: &mut [u64; 25], rounds: usize) {
+    // plain `u64`s need no instruction set
+    unsafe { keccakp(a, rounds) }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+#[target_feature(enable = "avx512f")]
+unsafe fn hash_leaves_avx512(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+    hash_leaves_n::<::core::arch::x86_64::__m512i>(rate, blocksize, input, buf)
+}
+
+#[cfg(target_arch = "x86_64")]
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+#[target_feature(enable = "avx2")]
+unsafe fn hash_leaves_avx2(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+    hash_leaves_n::<::core::arch::x86_64::__m256i>(rate, blocksize, input, buf)
+}
+
+fn hash_leaves_portable(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+    // SSE2 is part of x86_64
+    #[cfg(target_arch = "x86_64")]
+    type W = ::core::arch::x86_64::__m128i;
+    #[cfg(not(target_arch = "x86_64"))]
+    type W = [u64; 2];
+
+    unsafe { hash_leaves_n::<W>(rate, blocksize, input, buf) }
+}
+
+
+/// Number of leaves hashed together, callers should split work on multiples of it.
+pub(crate) const MAX_LANES: usize = 8;
+
+/// Hashes every `blocksize` chunk of `input` with `SHAKE` at the given `rate` (168 or 136 bytes),
+/// into the matching `200 - rate` bytes, the capacity, of `buf`.
+pub(crate) fn hash_leaves(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+    debug_assert!(rate == 168 || rate == 136);
+    debug_assert_eq!(input.len() % blocksize, 0);
+    debug_assert_eq!(input.len() / blocksize * (200 - rate), buf.len());
+
+    #[cfg(all(feature = "std", target_arch = "x86_64"))]
+    {
+        if is_x86_feature_detected!("avx512f") {
+            return unsafe { hash_leaves_avx512(rate, blocksize, input, buf) };
+        }
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { hash_leaves_avx2(rate, blocksize, input, buf) };
+        }
+    }
+
+    #[cfg(all(not(feature = "std"), target_arch = "x86_64", target_feature = "avx512f"))]
+    return unsafe { hash_leaves_avx512(rate, blocksize, input, buf) };
+
+    #[cfg(all(not(feature = "std"), target_arch = "x86_64", target_feature = "avx2", not(target_feature = "avx512f")))]
+    return unsafe { hash_leaves_avx2(rate, blocksize, input, buf) };
+
+    #[allow(unreachable_code)]
+    hash_leaves_portable(rate, blocksize, input, buf)
+}
+
+
+#[test]
+fn test_keccakf_lanes() {
+    let mut a = [[0; 2]; 25];
+    let mut scalar = [[0; 25]; 2];
+    for i in 0..25 {
+        for l in 0..2 {
+            a[i][l] = ((i as u64 + 1) * 0x0123_4567_89ab_cdef) ^ ((l as u64) << 7);
+            scalar[l][i] = a[i][l];
+        }
+    }
+
+    unsafe { keccakf(&mut a) };
+    for l in 0..2 {
+        ::tiny_keccak::keccakf(&mut scalar[l]);
+        for i in 0..25 {
+            assert_eq!(a[i][l], scalar[l][i]);
+        }
+    }
+}
+
+#[test]
+fn test_hash_leaves_lanes() {
+    fn scalar(rate: usize, blocksize: usize, input: &[u8], buf: &mut [u8]) {
+        let dlen = 200 - rate;
+        for (chunk, output) in input.chunks(blocksize).zip(buf.chunks_mut(dlen)) {
+            let mut shake = Keccak::new(rate, 0x1f);
+            shake.update(chunk);
+            shake.finalize(output);
+        }
+    }
+
+    let mut input = [0; 19 * 400];
+    for (i, b) in input.iter_mut().enumerate() {
+        *b = (i * 7 + 3) as u8;
+    }
+
+    for &rate in &[168, 136] {
+        let dlen = 200 - rate;
+        for &blocksize in &[1, 8, 135, 136, 168, 300, 400] {
+            let input = &input[..19 * blocksize];
+            let mut expected = [0; 19 * 64];
+            let expected = &mut expected[..19 * dlen];
+            scalar(rate, blocksize, input, expected);
+
+            let mut buf = [0; 19 * 64];
+            let buf = &mut buf[..19 * dlen];
+            unsafe { hash_leaves_n::<[u64; 2]>(rate, blocksize, input, buf) };
+            assert_eq!(buf, expected);
+            hash_leaves_portable(rate, blocksize, input, buf);
+            assert_eq!(buf, expected);
+            hash_leaves(rate, blocksize, input, buf);
+            assert_eq!(buf, expected);
+
+            #[cfg(all(feature = "std", target_arch = "x86_64"))]
+            {
+                if is_x86_feature_detected!("avx2") {
+                    unsafe { hash_leaves_avx2(rate, blocksize, input, buf) };
+                    assert_eq!(buf, expected);
+                }
+                if is_x86_feature_detected!("avx512f") {
+                    unsafe { hash_leaves_avx512(rate, blocksize, input, buf) };
+                    assert_eq!(buf, expected);
+                }
+            }
+        }
+    }
+}
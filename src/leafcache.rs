@@ -0,0 +1,366 @@
+//! Incremental `ParallelHash` of large files.
+
+use std::{ cmp, fs, io };
+use std::convert::TryFrom;
+use std::io::{ Read, Seek, SeekFrom, Write };
+use std::time::{ UNIX_EPOCH, Duration };
+use ::lanes;
+use ::hashid::hash_id;
+use ::parallelhash::ParallelHash;
+use ::parallelcore::{ new_leaf, leaf_digest };
+
+
+const MAGIC: &[u8; 16] = b"sp800-185 leaves";
+const VERSION: u8 = 1;
+const CHECKSUM_LEN: usize = 32;
+
+/// Bytes read and hashed at once while rehashing, a leaf longer than that is streamed.
+const READ_BYTES: usize = 1 << 24;
+
+
+/// Leaf digests of a `ParallelHash`, so that only modified leaves are rehashed.
+///
+/// The cache does not hold the data, it is told which ranges were written with `update_range`,
+/// and reads the leaves it cannot rehash from the written bytes alone in `digest`.
+///
+/// ```ignore
+/// let mut cache = LeafCache::new_leafcache256(b"", 1 << 20);
+/// cache.set_len(file.metadata()?.len());
+/// cache.digest(&mut file, &mut hash)?;
+///
+/// file.write_all_at(data, offset)?;
+/// cache.update_range(offset, data);
+/// cache.digest(&mut file, &mut hash)?;   // rehashes at most two leaves
+/// ```
+#[derive(Clone, Debug)]
+pub struct LeafCache {
+    custom: Vec<u8>,
+    blocksize: usize,
+    rate: usize,
+    len: u64,
+    digests: Vec<u8>,
+    dirty: Vec<bool>
+}
+
+impl LeafCache {
+    #[inline]
+    pub fn new_leafcache128(custom: &[u8], blocksize: usize) -> Self {
+        LeafCache::new(custom, blocksize, 128)
+    }
+
+    #[inline]
+    pub fn new_leafcache256(custom: &[u8], blocksize: usize) -> Self {
+        LeafCache::new(custom, blocksize, 256)
+    }
+
+    fn new(custom: &[u8], blocksize: usize, rate: usize) -> Self {
+        assert!(blocksize > 0, "blocksize must be positive");
+
+        LeafCache {
+            custom: custom.to_vec(),
+            blocksize,
+            rate,
+            len: 0,
+            digests: Vec::new(),
+            dirty: Vec::new()
+        }
+    }
+
+    /// Length of the data.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn blocksize(&self) -> usize {
+        self.blocksize
+    }
+
+    /// Number of leaves to rehash in the next `digest`.
+    pub fn dirty_leaves(&self) -> usize {
+        self.dirty.iter().filter(|&&dirty| dirty).count()
+    }
+
+    /// Resizes the data, as `File::set_len`.
+    ///
+    /// New leaves, and the last leaf if its length changed, are dirty.
+    pub fn set_len(&mut self, len: u64) {
+        let blocksize = self.blocksize as u64;
+        let old = self.leaves();
+        let new = len.div_ceil(blocksize) as usize;
+
+        self.digests.resize(new * self.dlen(), 0);
+        self.dirty.resize(new, true);
+
+        // the old last leaf grew, or the new last leaf was cut short
+        if len > self.len && !self.len.is_multiple_of(blocksize) {
+            self.dirty[old - 1] = true;
+        }
+        if len < self.len && !len.is_multiple_of(blocksize) {
+            self.dirty[new - 1] = true;
+        }
+
+        self.len = len;
+    }
+
+    /// Notes that `data` was written at `offset`, growing the data if it ends past it.
+    ///
+    /// Leaves covered by `data` are rehashed immediately, leaves it only overlaps are dirty.
+    pub fn update_range(&mut self, offset: u64, data: &[u8]) {
+        let end = offset + data.len() as u64;
+        if end > self.len {
+            self.set_len(end);
+        }
+        if data.is_empty() {
+            return;
+        }
+
+        let blocksize = self.blocksize as u64;
+        let dlen = self.dlen();
+        let first = (offset / blocksize) as usize;
+        let last = ((end - 1) / blocksize) as usize;
+
+        for i in first..=last {
+            let (start, stop) = self.leaf_range(i);
+            if offset <= start && stop <= end {
+                let chunk = &data[(start - offset) as usize..(stop - offset) as usize];
+                leaf_digest(self.rate, chunk, &mut self.digests[i * dlen..][..dlen]);
+                self.dirty[i] = false;
+            } else {
+                self.dirty[i] = true;
+            }
+        }
+    }
+
+    /// Rehashes the dirty leaves from `source`, and writes the `ParallelHash` of the data to `buf`.
+    pub fn digest<R: Read + Seek>(&mut self, mut source: R, buf: &mut [u8]) -> io::Result<()> {
+        let dlen = self.dlen();
+        let rate = 200 - dlen;
+        let run = cmp::max(1, READ_BYTES / self.blocksize);
+        // grows to the longest run read, at most `READ_BYTES`
+        let mut chunk = Vec::new();
+
+        let mut i = 0;
+        while i < self.dirty.len() {
+            if !self.dirty[i] {
+                i += 1;
+                continue
+            }
+
+            if self.blocksize > READ_BYTES {
+                let (start, stop) = self.leaf_range(i);
+                source.seek(SeekFrom::Start(start))?;
+                let mut leaf = new_leaf(self.rate);
+                let mut left = stop - start;
+                while left > 0 {
+                    let len = cmp::min(left, READ_BYTES as u64) as usize;
+                    if chunk.len() < len {
+                        chunk.resize(len, 0);
+                    }
+                    source.read_exact(&mut chunk[..len])?;
+                    leaf.update(&chunk[..len]);
+                    left -= len as u64;
+                }
+                leaf.finalize(&mut self.digests[i * dlen..][..dlen]);
+                self.dirty[i] = false;
+                i += 1;
+                continue
+            }
+
+            // a run of dirty leaves
+            let mut j = i + 1;
+            while j < self.dirty.len() && self.dirty[j] && j - i < run {
+                j += 1;
+            }
+
+            let start = self.leaf_range(i).0;
+            let stop = self.leaf_range(j - 1).1;
+            let len = (stop - start) as usize;
+            if chunk.len() < len {
+                chunk.resize(len, 0);
+            }
+            let chunk = &mut chunk[..len];
+            source.seek(SeekFrom::Start(start))?;
+            source.read_exact(chunk)?;
+
+            let full = chunk.len() / self.blocksize;
+            let (leaves, tail) = chunk.split_at(full * self.blocksize);
+            let digests = &mut self.digests[i * dlen..j * dlen];
+            let (digests, last) = digests.split_at_mut(full * dlen);
+            lanes::hash_leaves(rate, self.blocksize, leaves, digests);
+            if !tail.is_empty() {
+                leaf_digest(self.rate, tail, last);
+            }
+
+            for dirty in &mut self.dirty[i..j] {
+                *dirty = false;
+            }
+            i = j;
+        }
+
+        let n = self.leaves() as u64;
+        let digests = self.digests.chunks(dlen);
+        let hasher = if self.rate == 128 {
+            ParallelHash::from_leaf_digests128(&self.custom, self.blocksize, digests, n)
+        } else {
+            ParallelHash::from_leaf_digests256(&self.custom, self.blocksize, digests, n)
+        };
+        hasher.finalize(buf);
+
+        Ok(())
+    }
+
+    /// Writes the cache, stamped with the length and modification time of `file`.
+    pub fn save<W: Write>(&self, mut output: W, file: &fs::Metadata) -> io::Result<()> {
+        let modified = file.modified()?
+            .duration_since(UNIX_EPOCH)
+            .map_err(io::Error::other)?;
+
+        let mut body = Vec::with_capacity(64 + self.custom.len() + self.digests.len() + self.dirty.len() / 8);
+        body.extend_from_slice(MAGIC);
+        body.push(VERSION);
+        body.extend_from_slice(&(self.rate as u16).to_be_bytes());
+        body.extend_from_slice(&(self.blocksize as u64).to_be_bytes());
+        body.extend_from_slice(&(self.custom.len() as u64).to_be_bytes());
+        body.extend_from_slice(&self.custom);
+        body.extend_from_slice(&file.len().to_be_bytes());
+        body.extend_from_slice(&modified.as_secs().to_be_bytes());
+        body.extend_from_slice(&modified.subsec_nanos().to_be_bytes());
+        body.extend_from_slice(&self.len.to_be_bytes());
+        for dirty in self.dirty.chunks(8) {
+            body.push(dirty.iter().rev().fold(0, |acc, &dirty| (acc << 1) | dirty as u8));
+        }
+        body.extend_from_slice(&self.digests);
+
+        let mut checksum = [0; CHECKSUM_LEN];
+        hash_id(MAGIC, &body, &mut checksum);
+
+        output.write_all(&body)?;
+        output.write_all(&checksum)
+    }
+
+    /// Reads a cache written by `save`.
+    ///
+    /// Fails with `InvalidData` if the cache is corrupted, or stale: `file` is not the length, or
+    /// was modified at another time, than when the cache was saved.
+    pub fn load<R: Read>(mut input: R, file: &fs::Metadata) -> io::Result<Self> {
+        let mut body = Vec::new();
+        input.read_to_end(&mut body)?;
+
+        if body.len() < CHECKSUM_LEN {
+            return Err(invalid("truncated leaf cache"));
+        }
+        let (body, checksum) = body.split_at(body.len() - CHECKSUM_LEN);
+        let mut expected = [0; CHECKSUM_LEN];
+        hash_id(MAGIC, body, &mut expected);
+        if !::utils::ct_eq(checksum, &expected) {
+            return Err(invalid("corrupted leaf cache"));
+        }
+
+        let mut reader = Reader(body);
+        if reader.take(MAGIC.len())? != MAGIC || reader.take(1)? != [VERSION] {
+            return Err(invalid("not a leaf cache"));
+        }
+        let rate = reader.u16()? as usize;
+        let blocksize = reader.usize()?;
+        if !(rate == 128 || rate == 256) || blocksize == 0 {
+            return Err(invalid("not a leaf cache"));
+        }
+        let custom_len = reader.usize()?;
+        let custom = reader.take(custom_len)?.to_vec();
+        let file_len = reader.u64()?;
+        let (secs, nanos) = (reader.u64()?, reader.u32()?);
+        if nanos >= 1_000_000_000 {
+            return Err(invalid("not a leaf cache"));
+        }
+        let modified = UNIX_EPOCH.checked_add(Duration::new(secs, nanos))
+            .ok_or_else(|| invalid("not a leaf cache"))?;
+        let len = reader.u64()?;
+
+        if file_len != file.len() || modified != file.modified()? {
+            return Err(invalid("stale leaf cache"));
+        }
+
+        let mut cache = LeafCache::new(&custom, blocksize, rate);
+        cache.len = len;
+        let n = usize::try_from(len.div_ceil(blocksize as u64))
+            .map_err(|_| invalid("not a leaf cache"))?;
+        let digests_len = n.checked_mul(cache.dlen())
+            .ok_or_else(|| invalid("not a leaf cache"))?;
+        cache.dirty = reader.take(n.div_ceil(8))?.iter()
+            .flat_map(|&byte| (0..8).map(move |i| byte >> i & 1 == 1))
+            .take(n)
+            .collect();
+        cache.digests = reader.take(digests_len)?.to_vec();
+
+        if !reader.0.is_empty() {
+            return Err(invalid("not a leaf cache"));
+        }
+
+        Ok(cache)
+    }
+
+    #[inline]
+    fn dlen(&self) -> usize {
+        self.rate / 4
+    }
+
+    #[inline]
+    fn leaves(&self) -> usize {
+        self.len.div_ceil(self.blocksize as u64) as usize
+    }
+
+    /// Byte range of leaf `i`.
+    #[inline]
+    fn leaf_range(&self, i: usize) -> (u64, u64) {
+        let start = i as u64 * self.blocksize as u64;
+        (start, cmp::min(start + self.blocksize as u64, self.len))
+    }
+}
+
+
+#[inline]
+fn invalid(msg: &'static str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, msg)
+}
+
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> io::Result<&'a [u8]> {
+        if self.0.len() < n {
+            return Err(invalid("truncated leaf cache"));
+        }
+        let (head, tail) = self.0.split_at(n);
+        self.0 = tail;
+        Ok(head)
+    }
+
+    fn u16(&mut self) -> io::Result<u16> {
+        let mut buf = [0; 2];
+        buf.copy_from_slice(self.take(2)?);
+        Ok(u16::from_be_bytes(buf))
+    }
+
+    fn u32(&mut self) -> io::Result<u32> {
+        let mut buf = [0; 4];
+        buf.copy_from_slice(self.take(4)?);
+        Ok(u32::from_be_bytes(buf))
+    }
+
+    fn u64(&mut self) -> io::Result<u64> {
+        let mut buf = [0; 8];
+        buf.copy_from_slice(self.take(8)?);
+        Ok(u64::from_be_bytes(buf))
+    }
+
+    fn usize(&mut self) -> io::Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid("not a leaf cache"))
+    }
+}
@@ -1,20 +1,57 @@
 //! SHA-3 Derived Functions (SP800-185) Implementation in Rust.
 
 
-#![cfg_attr(not(feature = "parallelhash"), no_std)]
+#![cfg_attr(not(feature = "std"), no_std)]
 
-extern crate byteorder;
-extern crate tiny_keccak;
-#[cfg(feature = "parallelhash")] extern crate rayon;
+#[cfg(any(test, feature = "tiny-keccak"))] extern crate tiny_keccak;
+#[cfg(feature = "keccak")] extern crate keccak;
+#[cfg(feature = "std")] extern crate core;
+#[cfg(feature = "alloc")] extern crate alloc;
+#[cfg(feature = "rayon")] extern crate rayon;
+#[cfg(feature = "mmap")] extern crate memmap2;
 
 pub mod utils;
+pub mod hashid;
+pub mod h2c;
+pub mod function;
+pub mod midstate;
+#[cfg(feature = "recorder")] pub mod recorder;
+#[cfg(not(feature = "recorder"))] mod recorder;
+mod permutation;
+mod sponge;
+mod sample;
+mod shake;
 mod cshake;
+mod prefix;
 mod kmac;
+mod duplex;
 mod tuplehash;
+mod turboshake;
+mod kangarootwelve;
+mod transcript;
+mod element;
+mod lanes;
+mod parallelcore;
+mod fixed;
 
+pub use sponge::{ XofStream, Bytes, Blocks, Block };
+pub use shake::Shake;
 pub use cshake::CShake;
+pub use prefix::Prefix;
 pub use kmac::KMac;
+pub use duplex::Duplex;
 pub use tuplehash::TupleHash;
+pub use turboshake::TurboShake;
+pub use kangarootwelve::KangarooTwelve;
+pub use transcript::Transcript;
+pub use element::TupleElement;
+pub use fixed::FixedParallelHash;
 
-#[cfg(feature = "parallelhash")] mod parallelhash;
-#[cfg(feature = "parallelhash")] pub use parallelhash::ParallelHash;
+#[cfg(feature = "alloc")] mod executor;
+#[cfg(feature = "alloc")] mod parallelhash;
+#[cfg(feature = "alloc")] pub use executor::{ Executor, Leaves, Backend };
+#[cfg(feature = "alloc")] pub use parallelhash::{ ParallelHash, Progress, CancelToken, Cancelled };
+
+#[cfg(feature = "std")] mod leafcache;
+#[cfg(feature = "std")] pub use leafcache::LeafCache;
+#[cfg(feature = "std")] pub mod outboard;
@@ -0,0 +1,151 @@
+//! Versioned export of hashers in progress, to resume hashing after a restart.
+//!
+//! A midstate starts with the format version and the kind of hasher, then holds the current
+//! sponge and the one `reset` returns to, each as its 25 lanes, little-endian, followed by
+//! `rate / 8`, the absorb offset, the padding byte and the number of rounds. `ParallelHash` adds
+//! `blocksize`, `n`, the bytes absorbed and the length of the partial leaf as little-endian `u64`
+//! before those, and the sponge of the partial leaf after. Imports check all of it.
+//!
+//! A `KMac` midstate holds the state after the key, which is as good as the key. It is sealed
+//! with a key of its own, or exported in the clear with `dangerous_export_midstate`.
+
+use core::fmt;
+use ::duplex::Duplex;
+use ::utils::left_encode;
+
+
+/// Version of the format.
+///
+/// Version 1 held only the current sponge, version 2 adds the one `reset` returns to.
+pub const VERSION: u8 = 2;
+
+/// Length of a sponge in a midstate.
+pub(crate) const SPONGE_LEN: usize = 200 + 4;
+
+/// Length of the tag of a sealed midstate.
+const TAG_LEN: usize = 32;
+
+/// Length of a `CShake`, `TupleHash` or unsealed `KMac` midstate.
+pub const MIDSTATE_LEN: usize = 2 + 2 * SPONGE_LEN;
+
+/// Length of a sealed `KMac` midstate.
+pub const SEALED_LEN: usize = MIDSTATE_LEN + TAG_LEN;
+
+/// Length of a `ParallelHash` midstate.
+#[cfg(feature = "alloc")]
+pub const PARALLELHASH_LEN: usize = 2 + 4 * 8 + 3 * SPONGE_LEN;
+
+
+pub(crate) const KIND_CSHAKE: u8 = 1;
+pub(crate) const KIND_KMAC: u8 = 2;
+pub(crate) const KIND_TUPLEHASH: u8 = 3;
+#[cfg(feature = "alloc")]
+pub(crate) const KIND_PARALLELHASH: u8 = 4;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MidstateError {
+    /// The midstate is not of the expected length.
+    Length,
+    /// The midstate is of another version of the format.
+    Version,
+    /// The midstate is of another kind of hasher.
+    Kind,
+    /// The midstate holds a state no hasher can be in.
+    Invalid,
+    /// The sealed midstate was modified, or sealed with another key.
+    Tag
+}
+
+impl fmt::Display for MidstateError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(match *self {
+            MidstateError::Length => "midstate of the wrong length",
+            MidstateError::Version => "unsupported midstate version",
+            MidstateError::Kind => "midstate of another hasher",
+            MidstateError::Invalid => "invalid midstate",
+            MidstateError::Tag => "midstate failed authentication"
+        })
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for MidstateError {}
+
+
+#[inline]
+pub(crate) fn write_header(out: &mut [u8], kind: u8) {
+    out[0] = VERSION;
+    out[1] = kind;
+}
+
+/// Checks the length and the header, returns the body.
+pub(crate) fn check_header(buf: &[u8], kind: u8, len: usize) -> Result<&[u8], MidstateError> {
+    if buf.len() != len {
+        Err(MidstateError::Length)
+    } else if buf[0] != VERSION {
+        Err(MidstateError::Version)
+    } else if buf[1] != kind {
+        Err(MidstateError::Kind)
+    } else {
+        Ok(&buf[2..])
+    }
+}
+
+
+/// Seals a midstate as a deterministic AEAD: the tag is a MAC of the midstate, and the nonce of
+/// its encryption, so that the same midstate under the same key seals the same way.
+///
+/// The header stays in the clear, the tag follows it, then the encrypted body.
+pub(crate) fn seal(key: &[u8], midstate: &[u8; MIDSTATE_LEN]) -> [u8; SEALED_LEN] {
+    let mut sealed = [0; SEALED_LEN];
+    let (header, rest) = sealed.split_at_mut(2);
+    let (tag, body) = rest.split_at_mut(TAG_LEN);
+
+    header.copy_from_slice(&midstate[..2]);
+
+    let mut mac = keyed(key, b"tag");
+    mac.absorb(midstate);
+    mac.squeeze(tag);
+
+    body.copy_from_slice(&midstate[2..]);
+    let mut cipher = keyed(key, b"encrypt");
+    cipher.absorb(tag);
+    cipher.encrypt(body);
+
+    sealed
+}
+
+/// Opens a sealed midstate of `kind`.
+pub(crate) fn open(key: &[u8], sealed: &[u8], kind: u8) -> Result<[u8; MIDSTATE_LEN], MidstateError> {
+    let rest = check_header(sealed, kind, SEALED_LEN)?;
+    let (tag, body) = rest.split_at(TAG_LEN);
+
+    let mut midstate = [0; MIDSTATE_LEN];
+    midstate[..2].copy_from_slice(&sealed[..2]);
+    midstate[2..].copy_from_slice(body);
+
+    let mut cipher = keyed(key, b"encrypt");
+    cipher.absorb(tag);
+    cipher.decrypt(&mut midstate[2..]);
+
+    let mut mac = keyed(key, b"tag");
+    mac.absorb(&midstate);
+    if mac.verify(tag) {
+        Ok(midstate)
+    } else {
+        Err(MidstateError::Tag)
+    }
+}
+
+fn keyed(key: &[u8], purpose: &[u8]) -> Duplex {
+    let mut encbuf = [0; 9];
+    let mut duplex = Duplex::new_duplex256(b"Midstate", purpose);
+
+    // encode_string(K), then the next phase
+    let pos = left_encode(&mut encbuf, key.len() as u64 * 8);
+    duplex.absorb(&encbuf[pos..]);
+    duplex.absorb(key);
+    duplex.ratchet();
+    duplex
+}
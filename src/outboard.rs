@@ -0,0 +1,335 @@
+//! Verified streaming with `ParallelHash` leaves.
+//!
+//! An outboard holds the leaf digests of a blob, kept apart from the blob. Once the leaf list is
+//! checked against a trusted `ParallelHash` value, every block of the blob can be verified on its
+//! own, in any order, as it arrives.
+
+use std::{ cmp, fmt, io };
+use std::error::Error;
+use ::lanes;
+use ::parallelhash::ParallelHash;
+use ::parallelcore::leaf_digest;
+
+
+const MAGIC: &[u8; 16] = b"sp800-185 outbrd";
+const VERSION: u8 = 1;
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum VerifyError {
+    /// The outboard could not be parsed.
+    InvalidOutboard,
+    /// The leaf list does not hash to the trusted value.
+    RootMismatch,
+    /// The trusted value is shorter than 32 bytes for `ParallelHash128`, 64 for `ParallelHash256`.
+    ShortRoot,
+    /// The block with this index does not match its leaf digest.
+    BlockMismatch(u64),
+    /// The range is not made of whole blocks of the blob.
+    InvalidRange
+}
+
+impl fmt::Display for VerifyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            VerifyError::InvalidOutboard => f.write_str("invalid outboard"),
+            VerifyError::RootMismatch => f.write_str("root mismatch"),
+            VerifyError::ShortRoot => f.write_str("root too short"),
+            VerifyError::BlockMismatch(i) => write!(f, "block {} mismatch", i),
+            VerifyError::InvalidRange => f.write_str("invalid range")
+        }
+    }
+}
+
+impl Error for VerifyError {}
+
+impl From<VerifyError> for io::Error {
+    fn from(err: VerifyError) -> io::Error {
+        io::Error::new(io::ErrorKind::InvalidData, err)
+    }
+}
+
+
+/// The `ParallelHash` leaf digests of a blob.
+///
+/// The sender builds it with `new_outboard128` or `new_outboard256` and ships `to_bytes` along
+/// with the blob. The receiver, which trusts only the `ParallelHash` value `root`, parses it with
+/// `from_bytes`, then verifies blocks with `verify_block`, `verify_range` or `decoder`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Outboard {
+    custom: Vec<u8>,
+    blocksize: usize,
+    rate: usize,
+    len: u64,
+    digests: Vec<u8>
+}
+
+impl Outboard {
+    #[inline]
+    pub fn new_outboard128(custom: &[u8], blocksize: usize, data: &[u8]) -> Self {
+        Outboard::new(custom, blocksize, 128, data)
+    }
+
+    #[inline]
+    pub fn new_outboard256(custom: &[u8], blocksize: usize, data: &[u8]) -> Self {
+        Outboard::new(custom, blocksize, 256, data)
+    }
+
+    fn new(custom: &[u8], blocksize: usize, rate: usize, data: &[u8]) -> Self {
+        assert!(blocksize > 0, "blocksize must be positive");
+
+        let dlen = rate / 4;
+        let full = data.len() / blocksize;
+        let (leaves, tail) = data.split_at(full * blocksize);
+
+        let mut digests = vec![0; data.len().div_ceil(blocksize) * dlen];
+        let (full_digests, last) = digests.split_at_mut(full * dlen);
+        lanes::hash_leaves(200 - dlen, blocksize, leaves, full_digests);
+        if !tail.is_empty() {
+            leaf_digest(rate, tail, last);
+        }
+
+        Outboard {
+            custom: custom.to_vec(),
+            blocksize,
+            rate,
+            len: data.len() as u64,
+            digests
+        }
+    }
+
+    /// Parses an outboard, and checks its leaf list against the trusted `ParallelHash` value.
+    ///
+    /// The output length is absorbed by `ParallelHash`, so `root` must have the length it was
+    /// computed with, and at least the digest length of the leaves, 32 bytes for
+    /// `ParallelHash128` and 64 for `ParallelHash256`, so that it cannot be guessed.
+    pub fn from_bytes(bytes: &[u8], root: &[u8]) -> Result<Self, VerifyError> {
+        fn take<'a>(bytes: &mut &'a [u8], n: usize) -> Result<&'a [u8], VerifyError> {
+            if bytes.len() < n {
+                return Err(VerifyError::InvalidOutboard);
+            }
+            let (head, tail) = bytes.split_at(n);
+            *bytes = tail;
+            Ok(head)
+        }
+
+        fn u64(bytes: &mut &[u8]) -> Result<u64, VerifyError> {
+            let mut buf = [0; 8];
+            buf.copy_from_slice(take(bytes, 8)?);
+            Ok(u64::from_be_bytes(buf))
+        }
+
+        let mut bytes = bytes;
+        if take(&mut bytes, MAGIC.len())? != MAGIC || take(&mut bytes, 1)? != [VERSION] {
+            return Err(VerifyError::InvalidOutboard);
+        }
+        let rate = match take(&mut bytes, 2)? {
+            [0, 128] => 128,
+            [1, 0] => 256,
+            _ => return Err(VerifyError::InvalidOutboard)
+        };
+        if root.len() < rate / 4 {
+            return Err(VerifyError::ShortRoot);
+        }
+        let blocksize = u64(&mut bytes)?;
+        let len = u64(&mut bytes)?;
+        let custom_len = u64(&mut bytes)?;
+        if blocksize == 0 || blocksize > usize::MAX as u64 || custom_len > bytes.len() as u64 {
+            return Err(VerifyError::InvalidOutboard);
+        }
+        let custom = take(&mut bytes, custom_len as usize)?;
+
+        let n = len.div_ceil(blocksize);
+        if n.checked_mul(rate as u64 / 4) != Some(bytes.len() as u64) {
+            return Err(VerifyError::InvalidOutboard);
+        }
+
+        let outboard = Outboard {
+            custom: custom.to_vec(),
+            blocksize: blocksize as usize,
+            rate,
+            len,
+            digests: bytes.to_vec()
+        };
+
+        let mut expected = vec![0; root.len()];
+        outboard.root(&mut expected);
+        if ::utils::ct_eq(root, &expected) {
+            Ok(outboard)
+        } else {
+            Err(VerifyError::RootMismatch)
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(43 + self.custom.len() + self.digests.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.push(VERSION);
+        bytes.extend_from_slice(&(self.rate as u16).to_be_bytes());
+        bytes.extend_from_slice(&(self.blocksize as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.len.to_be_bytes());
+        bytes.extend_from_slice(&(self.custom.len() as u64).to_be_bytes());
+        bytes.extend_from_slice(&self.custom);
+        bytes.extend_from_slice(&self.digests);
+        bytes
+    }
+
+    /// The `ParallelHash` of the blob, computed from the leaf list.
+    pub fn root(&self, buf: &mut [u8]) {
+        let n = self.blocks();
+        let digests = self.digests.chunks(self.rate / 4);
+        let hasher = if self.rate == 128 {
+            ParallelHash::from_leaf_digests128(&self.custom, self.blocksize, digests, n)
+        } else {
+            ParallelHash::from_leaf_digests256(&self.custom, self.blocksize, digests, n)
+        };
+        hasher.finalize(buf);
+    }
+
+    /// Length of the blob.
+    #[inline]
+    pub fn len(&self) -> u64 {
+        self.len
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    #[inline]
+    pub fn blocksize(&self) -> usize {
+        self.blocksize
+    }
+
+    /// Number of blocks, the last one may be shorter.
+    #[inline]
+    pub fn blocks(&self) -> u64 {
+        self.len.div_ceil(self.blocksize as u64)
+    }
+
+    /// Checks block `index` of the blob.
+    pub fn verify_block(&self, index: u64, block: &[u8]) -> Result<(), VerifyError> {
+        if index >= self.blocks() || block.len() != self.block_len(index) {
+            return Err(VerifyError::InvalidRange);
+        }
+
+        let dlen = self.rate / 4;
+        let mut z = [0; 64];
+        leaf_digest(self.rate, block, &mut z[..dlen]);
+
+        let start = index as usize * dlen;
+        if ::utils::ct_eq(&z[..dlen], &self.digests[start..][..dlen]) {
+            Ok(())
+        } else {
+            Err(VerifyError::BlockMismatch(index))
+        }
+    }
+
+    /// Checks `data` at `offset` of the blob, which must span whole blocks.
+    pub fn verify_range(&self, offset: u64, data: &[u8]) -> Result<(), VerifyError> {
+        let blocksize = self.blocksize as u64;
+        let end = offset.checked_add(data.len() as u64);
+        if !offset.is_multiple_of(blocksize) || end.is_none_or(|end| end > self.len) {
+            return Err(VerifyError::InvalidRange);
+        }
+
+        let first = offset / blocksize;
+        let mut data = data;
+        for index in first.. {
+            if data.is_empty() {
+                break
+            }
+
+            let len = cmp::min(self.block_len(index), data.len());
+            let (block, rest) = data.split_at(len);
+            self.verify_block(index, block)?;
+            data = rest;
+        }
+
+        Ok(())
+    }
+
+    /// Streams the blob into `output`, verifying each block before it is written.
+    ///
+    /// A block that does not match fails the `write` that completes it, and nothing of it
+    /// reaches `output`.
+    #[inline]
+    pub fn decoder<W: io::Write>(&self, output: W) -> Decoder<'_, W> {
+        Decoder {
+            outboard: self,
+            output,
+            buf: Vec::with_capacity(self.blocksize),
+            index: 0,
+            failed: false
+        }
+    }
+
+    #[inline]
+    fn block_len(&self, index: u64) -> usize {
+        let start = index * self.blocksize as u64;
+        cmp::min(self.blocksize as u64, self.len.saturating_sub(start)) as usize
+    }
+}
+
+
+/// Verifying writer returned by `Outboard::decoder`.
+pub struct Decoder<'a, W> {
+    outboard: &'a Outboard,
+    output: W,
+    buf: Vec<u8>,
+    index: u64,
+    failed: bool
+}
+
+impl<'a, W: io::Write> Decoder<'a, W> {
+    /// Checks that the whole blob was received, and returns the output.
+    pub fn finish(mut self) -> io::Result<W> {
+        if self.failed {
+            return Err(VerifyError::BlockMismatch(self.index).into());
+        }
+        if self.index != self.outboard.blocks() {
+            return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "blob is truncated"));
+        }
+
+        self.output.flush()?;
+        Ok(self.output)
+    }
+}
+
+impl<'a, W: io::Write> io::Write for Decoder<'a, W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.failed {
+            return Err(VerifyError::BlockMismatch(self.index).into());
+        }
+        if self.index >= self.outboard.blocks() {
+            return if data.is_empty() {
+                Ok(0)
+            } else {
+                Err(VerifyError::InvalidRange.into())
+            };
+        }
+
+        let block_len = self.outboard.block_len(self.index);
+        let len = cmp::min(block_len - self.buf.len(), data.len());
+        self.buf.extend_from_slice(&data[..len]);
+
+        if self.buf.len() == block_len {
+            if let Err(err) = self.outboard.verify_block(self.index, &self.buf) {
+                self.failed = true;
+                return Err(err.into());
+            }
+
+            self.output.write_all(&self.buf)?;
+            self.buf.clear();
+            self.index += 1;
+        }
+
+        Ok(len)
+    }
+
+    #[inline]
+    fn flush(&mut self) -> io::Result<()> {
+        self.output.flush()
+    }
+}
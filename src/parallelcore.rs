@@ -0,0 +1,157 @@
+use core::{ cmp, mem };
+use ::sponge::{ Sponge, XofStream };
+use ::cshake::CShake;
+use ::utils::{ left_encode, right_encode };
+
+
+/// The part of `ParallelHash` shared with `FixedParallelHash`: the outer `cSHAKE`, the
+/// unfinished leaf and the leaf count. How full leaves are hashed is up to the caller.
+#[derive(Clone)]
+pub(crate) struct ParallelCore {
+    pub(crate) inner: CShake,
+    /// The unfinished leaf, `cSHAKE(chunk, rate, "", "")` over its first `partial_len` bytes,
+    /// which is `SHAKE`.
+    pub(crate) partial: Sponge,
+    pub(crate) partial_len: usize,
+    pub(crate) n: u64,
+    pub(crate) rate: usize
+}
+
+impl ParallelCore {
+    /// Absorbs `left_encode(B)` into `inner`, a fresh `cSHAKE` of `ParallelHash`.
+    pub(crate) fn new(mut inner: CShake, rate: usize, blocksize: usize) -> Self {
+        let mut encbuf = [0; 9];
+        let pos = left_encode(&mut encbuf, blocksize as u64);
+        inner.absorb("left_encode(B)", &encbuf[pos..]);
+        inner.set_init();
+
+        ParallelCore {
+            inner,
+            partial: new_leaf(rate),
+            partial_len: 0,
+            n: 0,
+            rate
+        }
+    }
+
+    /// Absorbs `input` in leaves of `blocksize`, returns the number of bytes absorbed.
+    ///
+    /// The partial leaf is completed first, then `hash_leaves` gets the full leaves that follow
+    /// and the number of bytes before them. It absorbs their digests with `absorb_digests`, and
+    /// returns how many bytes of leaves it took; the rest of `input` is dropped if not all.
+    pub(crate) fn update<F>(&mut self, blocksize: usize, input: &[u8], mut hash_leaves: F) -> usize
+        where F: FnMut(&mut ParallelCore, &[u8], usize) -> usize
+    {
+        let mut buf = input;
+
+        if self.partial_len > 0 {
+            let len = cmp::min(blocksize - self.partial_len, buf.len());
+            self.partial.update(&buf[..len]);
+            self.partial_len += len;
+            buf = &buf[len..];
+
+            if self.partial_len < blocksize {
+                return input.len();
+            }
+            self.finish_partial();
+        }
+
+        let end = buf.len() - buf.len() % blocksize;
+        let (leaves, tail) = buf.split_at(end);
+        let before = input.len() - buf.len();
+
+        if !leaves.is_empty() {
+            let consumed = hash_leaves(self, leaves, before);
+            if consumed < leaves.len() {
+                return before + consumed;
+            }
+        }
+
+        if !tail.is_empty() {
+            self.partial.update(tail);
+            self.partial_len = tail.len();
+        }
+
+        input.len()
+    }
+
+    /// Absorbs the `rate / 4` bytes digests of the next leaves.
+    #[inline]
+    pub(crate) fn absorb_digests(&mut self, digests: &[u8]) {
+        let dlen = self.rate / 4;
+        for z in digests.chunks(dlen) {
+            self.inner.absorb("z[i]", z);
+            self.n += 1;
+        }
+    }
+
+    #[inline]
+    pub(crate) fn finalize(mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.inner.finalize(buf)
+    }
+
+    #[inline]
+    pub(crate) fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.inner.finalize(buf);
+        self.reset();
+    }
+
+    /// Back to the state after `left_encode(B)`.
+    pub(crate) fn reset(&mut self) {
+        self.inner.reset();
+        self.partial = new_leaf(self.rate);
+        self.partial_len = 0;
+        self.n = 0;
+    }
+
+    #[inline]
+    pub(crate) fn xof(mut self) -> XofStream {
+        self.with_bitlength(0);
+        self.inner.xof()
+    }
+
+    /// Squeezes the partial leaf and starts a new one.
+    fn finish_partial(&mut self) {
+        let mut encbuf = [0; 64];
+        let encbuf = &mut encbuf[..self.rate / 4];
+
+        let shake = mem::replace(&mut self.partial, new_leaf(self.rate));
+        shake.finalize(encbuf);
+        self.inner.absorb("z[i]", encbuf);
+        self.partial_len = 0;
+        self.n += 1;
+    }
+
+    #[inline]
+    fn with_bitlength(&mut self, bitlength: u64) {
+        if self.partial_len > 0 {
+            self.finish_partial();
+        }
+
+        let mut encbuf = [0; 9];
+
+        let pos = right_encode(&mut encbuf, self.n);
+        self.inner.absorb("right_encode(n)", &encbuf[pos..]);
+
+        let pos = right_encode(&mut encbuf, bitlength);
+        self.inner.absorb("right_encode(L)", &encbuf[pos..]);
+    }
+}
+
+
+/// An empty leaf of `ParallelHash128` or `ParallelHash256`.
+#[inline]
+pub(crate) fn new_leaf(rate: usize) -> Sponge {
+    Sponge::new(200 - rate / 4, 0x1f)
+}
+
+/// The `rate / 4` bytes digest of one leaf.
+#[cfg(feature = "alloc")]
+#[inline]
+pub(crate) fn leaf_digest(rate: usize, chunk: &[u8], buf: &mut [u8]) {
+    let mut shake = new_leaf(rate);
+    shake.update(chunk);
+    shake.finalize(buf);
+}
@@ -1,7 +1,79 @@
-use tiny_keccak::{ Keccak, XofReader };
-use rayon::prelude::*;
+use core::{ cmp, fmt };
+use core::convert::TryFrom;
+use core::sync::atomic::{ AtomicBool, AtomicUsize, Ordering };
+use alloc::vec::Vec;
+use alloc::sync::Arc;
+use ::sponge::{ Sponge, XofStream };
 use ::cshake::CShake;
-use ::utils::{ left_encode, right_encode };
+use ::executor::{ Executor, Leaves, Backend };
+use ::parallelcore::{ ParallelCore, new_leaf, leaf_digest };
+use ::midstate::{ self, MidstateError, SPONGE_LEN, PARALLELHASH_LEN, KIND_PARALLELHASH };
+#[cfg(feature = "std")] use std::io;
+#[cfg(feature = "std")] use std::path::Path;
+
+
+/// Default `with_min_batch`.
+const MIN_BATCH: usize = 4 * Leaves::LANES;
+
+/// Default `with_max_in_flight`.
+const MAX_IN_FLIGHT: usize = 1024;
+
+/// Size of each `update_reader` buffer, rounded down to whole leaves.
+#[cfg(feature = "std")]
+const READ_BUF: usize = 1 << 20;
+
+
+#[derive(Clone)]
+enum Exec {
+    Backend(Backend),
+    Custom(Arc<dyn Executor>)
+}
+
+
+/// Input absorbed by a `ParallelHash` so far.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Progress {
+    /// Bytes passed to `update`, including those of the unfinished leaf.
+    pub bytes: u64,
+    /// Leaves hashed.
+    pub leaves: u64
+}
+
+/// Shared flag that stops `ParallelHash::try_update` and the reader entry points.
+#[derive(Clone, Debug, Default)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    #[inline]
+    pub fn new() -> Self {
+        CancelToken::default()
+    }
+
+    #[inline]
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed)
+    }
+
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+}
+
+/// `try_update` was cancelled after absorbing the first `consumed` bytes.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Cancelled {
+    pub consumed: usize
+}
+
+impl fmt::Display for Cancelled {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cancelled after {} bytes", self.consumed)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Cancelled {}
 
 
 /// Parallel Hash.
@@ -14,99 +86,390 @@ use ::utils::{ left_encode, right_encode };
 /// customization strings.
 #[derive(Clone)]
 pub struct ParallelHash {
-    inner: CShake,
-    buf: Vec<u8>,
-    n: u64,
-    rate: usize,
-    blocksize: usize
+    core: ParallelCore,
+    digests: Vec<u8>,
+    blocksize: usize,
+    executor: Exec,
+    min_batch: usize,
+    max_in_flight: usize,
+    bytes: u64,
+    progress: Option<Arc<dyn Fn(Progress) + Send + Sync>>,
+    cancel: Option<CancelToken>
 }
 
 impl ParallelHash {
     #[inline]
     pub fn new_parallelhash128(custom: &[u8], blocksize: usize) -> Self {
-        let mut hasher = ParallelHash {
-            inner: CShake::new_cshake128(b"ParallelHash", custom),
-            buf: Vec::new(),
-            n: 0,
-            rate: 128,
-            blocksize
-        };
-        hasher.init();
-        hasher
+        assert!(blocksize > 0, "blocksize must be positive");
+
+        ParallelHash {
+            core: ParallelCore::new(CShake::new_cshake128(b"ParallelHash", custom), 128, blocksize),
+            digests: Vec::new(),
+            blocksize,
+            executor: Exec::Backend(Backend::default()),
+            min_batch: MIN_BATCH,
+            max_in_flight: MAX_IN_FLIGHT,
+            bytes: 0,
+            progress: None,
+            cancel: None
+        }
     }
 
     #[inline]
     pub fn new_parallelhash256(custom: &[u8], blocksize: usize) -> Self {
-        let mut hasher = ParallelHash {
-            inner: CShake::new_cshake256(b"ParallelHash", custom),
-            buf: Vec::new(),
-            n: 0,
-            rate: 256,
-            blocksize
-        };
-        hasher.init();
+        assert!(blocksize > 0, "blocksize must be positive");
+
+        ParallelHash {
+            core: ParallelCore::new(CShake::new_cshake256(b"ParallelHash", custom), 256, blocksize),
+            digests: Vec::new(),
+            blocksize,
+            executor: Exec::Backend(Backend::default()),
+            min_batch: MIN_BATCH,
+            max_in_flight: MAX_IN_FLIGHT,
+            bytes: 0,
+            progress: None,
+            cancel: None
+        }
+    }
+
+    /// `ParallelHash128` over leaves hashed elsewhere with `leaf_digest128`.
+    ///
+    /// `digests` are the digests of the `blocksize` chunks of the input, in order, the last chunk
+    /// may be shorter. The result can be finalized. It can be updated with the input that follows
+    /// only if every chunk is full, otherwise the output is not the `ParallelHash` of the whole
+    /// input. `progress` counts every leaf as `blocksize` bytes.
+    ///
+    /// Panics if a digest is not 32 bytes, or if there are not `total_leaves` of them.
+    pub fn from_leaf_digests128<I>(custom: &[u8], blocksize: usize, digests: I, total_leaves: u64) -> Self
+        where I: IntoIterator, I::Item: AsRef<[u8]>
+    {
+        let mut hasher = ParallelHash::new_parallelhash128(custom, blocksize);
+        hasher.absorb_leaf_digests(digests, total_leaves);
         hasher
     }
 
-    fn init(&mut self) {
-        let mut encbuf = [0; 9];
+    /// `ParallelHash256` over leaves hashed elsewhere with `leaf_digest256`.
+    ///
+    /// Panics if a digest is not 64 bytes, or if there are not `total_leaves` of them.
+    pub fn from_leaf_digests256<I>(custom: &[u8], blocksize: usize, digests: I, total_leaves: u64) -> Self
+        where I: IntoIterator, I::Item: AsRef<[u8]>
+    {
+        let mut hasher = ParallelHash::new_parallelhash256(custom, blocksize);
+        hasher.absorb_leaf_digests(digests, total_leaves);
+        hasher
+    }
+
+    /// The digest of one leaf of `ParallelHash128`, `cSHAKE128(chunk, 256, "", "")`.
+    pub fn leaf_digest128(chunk: &[u8]) -> [u8; 32] {
+        let mut z = [0; 32];
+        leaf_digest(128, chunk, &mut z);
+        z
+    }
 
-        // left_encode(B)
-        let pos = left_encode(&mut encbuf, self.blocksize as u64);
-        self.inner.update(&encbuf[pos..]);
+    /// The digest of one leaf of `ParallelHash256`, `cSHAKE256(chunk, 512, "", "")`.
+    pub fn leaf_digest256(chunk: &[u8]) -> [u8; 64] {
+        let mut z = [0; 64];
+        leaf_digest(256, chunk, &mut z);
+        z
+    }
+
+    fn absorb_leaf_digests<I>(&mut self, digests: I, total_leaves: u64)
+        where I: IntoIterator, I::Item: AsRef<[u8]>
+    {
+        for z in digests {
+            let z = z.as_ref();
+            assert_eq!(z.len(), self.core.rate / 4, "leaf digest of the wrong length");
+            self.core.absorb_digests(z);
+        }
+        assert_eq!(self.core.n, total_leaves, "missing or extra leaf digests");
+        self.bytes = total_leaves.saturating_mul(self.blocksize as u64);
+    }
+
+    /// The state, to resume hashing with `import_midstate`.
+    ///
+    /// The backend, executor and callbacks are not part of it.
+    pub fn export_midstate(&self) -> [u8; PARALLELHASH_LEN] {
+        let mut out = [0; PARALLELHASH_LEN];
+        midstate::write_header(&mut out, KIND_PARALLELHASH);
+
+        let (params, sponges) = out[2..].split_at_mut(4 * 8);
+        let values = [self.blocksize as u64, self.core.n, self.bytes, self.core.partial_len as u64];
+        for (chunk, value) in params.chunks_mut(8).zip(values.iter()) {
+            chunk.copy_from_slice(&value.to_le_bytes());
+        }
+
+        let (inner, partial) = sponges.split_at_mut(2 * SPONGE_LEN);
+        self.core.inner.export_sponge(inner);
+        self.core.partial.export(partial);
+        out
+    }
+
+    /// A hasher with the default settings, in the exported state.
+    pub fn import_midstate(buf: &[u8]) -> Result<Self, MidstateError> {
+        let body = midstate::check_header(buf, KIND_PARALLELHASH, PARALLELHASH_LEN)?;
+
+        let (params, sponges) = body.split_at(4 * 8);
+        let mut values = [0; 4];
+        for (value, chunk) in values.iter_mut().zip(params.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            *value = u64::from_le_bytes(bytes);
+        }
+        let [blocksize, n, bytes, partial_len] = values;
+
+        let (inner, partial) = sponges.split_at(2 * SPONGE_LEN);
+        let inner = CShake::import_sponge(inner)?;
+        let partial = Sponge::import(partial, 0x1f, 24)?;
+        let rate = if inner.rate() == 168 { 128 } else { 256 };
+
+        let blocksize = usize::try_from(blocksize).map_err(|_| MidstateError::Invalid)?;
+        let partial_len = usize::try_from(partial_len).map_err(|_| MidstateError::Invalid)?;
+        let total = n.checked_mul(blocksize as u64)
+            .and_then(|total| total.checked_add(partial_len as u64));
+        if blocksize == 0 || partial_len >= blocksize || total != Some(bytes)
+            || partial.rate() != inner.rate() || partial_len % partial.rate() != partial.offset()
+        {
+            return Err(MidstateError::Invalid);
+        }
+
+        Ok(ParallelHash {
+            core: ParallelCore {
+                inner,
+                partial: if partial_len == 0 { new_leaf(rate) } else { partial },
+                partial_len,
+                n,
+                rate
+            },
+            digests: Vec::new(),
+            blocksize,
+            executor: Exec::Backend(Backend::default()),
+            min_batch: MIN_BATCH,
+            max_in_flight: MAX_IN_FLIGHT,
+            bytes,
+            progress: None,
+            cancel: None
+        })
+    }
+
+    /// Selects the backend that hashes the leaves.
+    #[inline]
+    pub fn with_backend(mut self, backend: Backend) -> Self {
+        self.executor = Exec::Backend(backend);
+        self
+    }
+
+    /// Hashes the leaves on `executor`, such as a `rayon::ThreadPool`.
+    ///
+    /// Wrap it in an `Arc` to share it between hashers.
+    #[inline]
+    pub fn with_executor<E: Executor + 'static>(mut self, executor: E) -> Self {
+        self.executor = Exec::Custom(Arc::new(executor));
+        self
     }
 
+    /// Hashes batches of fewer than `leaves` leaves on the calling thread, default 32.
+    #[inline]
+    pub fn with_min_batch(mut self, leaves: usize) -> Self {
+        self.min_batch = leaves;
+        self
+    }
+
+    /// Hands at most `leaves` leaves to the executor at once, default 1024.
+    ///
+    /// The input is not copied, but the digests of a batch are buffered, 64 bytes per leaf.
+    #[inline]
+    pub fn with_max_in_flight(mut self, leaves: usize) -> Self {
+        assert!(leaves > 0, "max in-flight leaves must be positive");
+        self.max_in_flight = leaves;
+        self
+    }
+
+    /// Calls `f` with the totals after each batch of leaves.
+    #[inline]
+    pub fn with_progress<F>(mut self, f: F) -> Self
+        where F: Fn(Progress) + Send + Sync + 'static
+    {
+        self.progress = Some(Arc::new(f));
+        self
+    }
+
+    /// Checks `token` before each batch of leaves in `try_update`, `update_reader` and `hash_file`.
+    ///
+    /// Only those are cancellable: `update` cannot report how much it absorbed, so it ignores the
+    /// token and absorbs all of its input.
+    #[inline]
+    pub fn with_cancel(mut self, token: CancelToken) -> Self {
+        self.cancel = Some(token);
+        self
+    }
+
+    #[inline]
+    pub fn progress(&self) -> Progress {
+        Progress { bytes: self.bytes, leaves: self.core.n }
+    }
+
+    /// Absorbs all of `buf`, whether or not the `with_cancel` token is cancelled.
+    #[inline]
     pub fn update(&mut self, buf: &[u8]) {
-        let rate = self.rate;
-
-        let pos = if !self.buf.is_empty() {
-            let len = self.blocksize - self.buf.len();
-
-            if buf.len() < len {
-                self.buf.extend_from_slice(buf);
-
-                return;
-            } else {
-                let mut encbuf = vec![0; rate / 4];
-                let mut shake = Keccak::new(200 - rate / 4, 0x1f);
-                shake.update(&self.buf);
-                shake.update(&buf[..len]);
-                shake.finalize(&mut encbuf);
-                self.inner.update(&encbuf);
-                self.buf.clear();
-                self.n += 1;
-            }
-            len
+        self.absorb_input(buf, false);
+    }
+
+    /// `update`, unless the `with_cancel` token is cancelled before a batch of leaves.
+    ///
+    /// A cancelled hasher has absorbed `buf[..consumed]` and nothing else, it can be resumed with
+    /// the rest of the input, or dropped.
+    #[inline]
+    pub fn try_update(&mut self, buf: &[u8]) -> Result<(), Cancelled> {
+        let consumed = self.absorb_input(buf, true);
+        if consumed == buf.len() {
+            Ok(())
         } else {
-            0
-        };
-
-        let bufs = buf[pos..].par_chunks(self.blocksize)
-            .map(|chunk| if chunk.len() < self.blocksize {
-                (false, chunk.into())
-            } else {
-                // cSHAKE(chunk, rate, "", "")
-                let mut encbuf = vec![0; rate / 4];
-                let mut shake = Keccak::new(200 - rate / 4, 0x1f);
-                shake.update(chunk);
-                shake.finalize(&mut encbuf);
-                (true, encbuf)
-            })
-            .collect::<Vec<_>>();
-        for (is_hashed, mut buf) in bufs {
-            if is_hashed {
-                self.inner.update(&buf);
-                self.n += 1;
-            } else {
-                self.buf.append(&mut buf);
+            Err(Cancelled { consumed })
+        }
+    }
+
+    /// Returns the number of bytes absorbed, all of `input` unless cancelled.
+    fn absorb_input(&mut self, input: &[u8], cancellable: bool) -> usize {
+        let ParallelHash {
+            ref mut core, ref mut digests, blocksize, ref executor, min_batch, max_in_flight,
+            bytes, ref progress, ref cancel
+        } = *self;
+
+        let consumed = core.update(blocksize, input, |core, leaves, before| {
+            let dlen = core.rate / 4;
+            let batch = cmp::min(leaves.len() / blocksize, max_in_flight);
+            if digests.len() < batch * dlen {
+                digests.resize(batch * dlen, 0);
+            }
+
+            let mut consumed = 0;
+            for leaves in leaves.chunks(max_in_flight * blocksize) {
+                if cancellable && cancel.as_ref().is_some_and(CancelToken::is_cancelled) {
+                    break;
+                }
+
+                let count = leaves.len() / blocksize;
+                let digests = &mut digests[..count * dlen];
+                let hashed = AtomicUsize::new(0);
+                let batch = Leaves::new(200 - dlen, blocksize, leaves, digests, &hashed);
+                match *executor {
+                    _ if count < min_batch => batch.hash(),
+                    Exec::Backend(ref backend) => backend.execute(batch),
+                    Exec::Custom(ref executor) => executor.execute(batch)
+                }
+                assert_eq!(hashed.into_inner(), count, "executor returned with leaves not hashed");
+
+                core.absorb_digests(digests);
+                consumed += leaves.len();
+
+                if let Some(ref progress) = *progress {
+                    progress(Progress { bytes: bytes + (before + consumed) as u64, leaves: core.n });
+                }
+            }
+            consumed
+        });
+
+        self.bytes += consumed as u64;
+        consumed
+    }
+
+    /// Reads `reader` to the end and absorbs everything, returns the number of bytes read.
+    ///
+    /// Reads overlap hashing: the calling thread fills one buffer while a scoped thread hashes
+    /// the other. Buffers hold whole leaves, so the output is the same as `update` on the bytes.
+    ///
+    /// On a read error, every byte read before it has been absorbed. If cancelled, the error wraps
+    /// `Cancelled`, and bytes read but not yet hashed are dropped. Either way the input absorbed
+    /// is `progress().bytes` in total, so a seekable reader can be resumed from there.
+    #[cfg(feature = "std")]
+    pub fn update_reader<R: io::Read>(&mut self, mut reader: R) -> io::Result<u64> {
+        use std::{ panic, thread };
+        use std::sync::mpsc::sync_channel;
+
+        let size = cmp::max(1, READ_BUF / self.blocksize) * self.blocksize;
+
+        thread::scope(|s| {
+            let (full_tx, full_rx) = sync_channel::<(Vec<u8>, usize)>(1);
+            let (empty_tx, empty_rx) = sync_channel::<Vec<u8>>(2);
+            for _ in 0..2 {
+                let _ = empty_tx.send(vec![0; size]);
+            }
+
+            let hasher = s.spawn(move || {
+                for (buf, len) in full_rx {
+                    self.try_update(&buf[..len])?;
+                    let _ = empty_tx.send(buf);
+                }
+                Ok::<_, Cancelled>(())
+            });
+
+            let mut total = 0;
+            let read = loop {
+                let mut buf = match empty_rx.recv() {
+                    Ok(buf) => buf,
+                    Err(_) => break Ok(())
+                };
+                let (len, result) = read_full(&mut reader, &mut buf);
+                total += len as u64;
+
+                if len > 0 && full_tx.send((buf, len)).is_err() {
+                    break Ok(())
+                }
+                if result.is_err() || len < size {
+                    break result
+                }
+            };
+            drop(full_tx);
+
+            match hasher.join() {
+                Ok(Ok(())) => read.map(|()| total),
+                Ok(Err(cancelled)) => Err(io::Error::other(cancelled)),
+                Err(err) => panic::resume_unwind(err)
+            }
+        })
+    }
+
+    /// Absorbs the content of the file at `path`, returns its length.
+    ///
+    /// With the `mmap` feature, regular files are memory-mapped and hashed in place, and must
+    /// not be modified meanwhile. Otherwise this is `update_reader` on the file.
+    #[cfg(feature = "std")]
+    pub fn hash_file<P: AsRef<Path>>(&mut self, path: P) -> io::Result<u64> {
+        use std::fs::File;
+
+        let file = File::open(path)?;
+
+        #[cfg(feature = "mmap")] {
+            let metadata = file.metadata()?;
+            if metadata.is_file() && metadata.len() > 0 {
+                let map = unsafe { ::memmap2::Mmap::map(&file)? };
+                #[cfg(unix)]
+                let _ = map.advise(::memmap2::Advice::Sequential);
+
+                self.try_update(&map).map_err(io::Error::other)?;
+                return Ok(map.len() as u64);
             }
         }
+
+        self.update_reader(file)
     }
 
     #[inline]
-    pub fn finalize(mut self, buf: &mut [u8]) {
-        self.with_bitlength(buf.len() as u64 * 8);
-        self.inner.finalize(buf)
+    pub fn finalize(self, buf: &mut [u8]) {
+        self.core.finalize(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.core.finalize_reset(buf);
+        self.bytes = 0;
+    }
+
+    /// Back to the state after `left_encode(B)`, keeping the backend, executor and callbacks.
+    pub fn reset(&mut self) {
+        self.core.reset();
+        self.bytes = 0;
     }
 
     /// A function on bit strings in which the output can be extended to  any desired length.
@@ -116,32 +479,23 @@ impl ParallelHash {
     /// XOF (i.e., the output can be extended to any desired length), which mimics the behavior of
     /// cSHAKE.
     #[inline]
-    pub fn xof(mut self) -> XofReader {
-        self.with_bitlength(0);
-        self.inner.xof()
+    pub fn xof(self) -> XofStream {
+        self.core.xof()
     }
+}
 
-    #[inline]
-    fn with_bitlength(&mut self, bitlength: u64) {
-        if !self.buf.is_empty() {
-            let mut encbuf = vec![0; self.rate / 4];
-            let mut shake = Keccak::new(200 - self.rate / 4, 0x1f);
-            shake.update(&self.buf);
-            shake.finalize(&mut encbuf);
-            self.inner.update(&encbuf);
-            self.buf.clear();
-            self.n += 1;
-        }
-
-
-        let mut encbuf = [0; 9];
-
-        // right_encode(n)
-        let pos = right_encode(&mut encbuf, self.n);
-        self.inner.update(&encbuf[pos..]);
 
-        // right_encode(L)
-        let pos = right_encode(&mut encbuf, bitlength);
-        self.inner.update(&encbuf[pos..]);
+/// Reads until `buf` is full, the end of `reader` or an error, returns the bytes read either way.
+#[cfg(feature = "std")]
+fn read_full<R: io::Read>(reader: &mut R, buf: &mut [u8]) -> (usize, io::Result<()>) {
+    let mut len = 0;
+    while len < buf.len() {
+        match reader.read(&mut buf[len..]) {
+            Ok(0) => break,
+            Ok(n) => len += n,
+            Err(ref err) if err.kind() == io::ErrorKind::Interrupted => (),
+            Err(err) => return (len, Err(err))
+        }
     }
+    (len, Ok(()))
 }
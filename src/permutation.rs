@@ -0,0 +1,118 @@
+//! `Keccak-f[1600]` backends.
+//!
+//! Every sponge of the crate is permuted by one backend, chosen at compile time: the RustCrypto
+//! `keccak` crate with the `keccak` feature, else `tiny-keccak` with the `tiny-keccak` feature,
+//! else the built-in permutation. The built-in one is the portable code of `lanes` on a single
+//! `u64`, with the rounds unrolled but nothing specific to a target; measure the backends on the
+//! target rather than assume it is the fastest. The multi-lane `ParallelHash` leaves always use
+//! `lanes`.
+
+use ::lanes;
+
+
+pub(crate) trait Permutation {
+    fn keccakf(state: &mut [u64; 25]);
+
+    /// `Keccak-p[1600, 12]`, the last 12 rounds of `Keccak-f[1600]`.
+    #[inline]
+    fn keccakp12(state: &mut [u64; 25]) {
+        lanes::keccakp1600(state, 12)
+    }
+}
+
+#[cfg_attr(any(feature = "keccak", feature = "tiny-keccak"), allow(dead_code))]
+pub(crate) struct Builtin;
+
+impl Permutation for Builtin {
+    #[inline]
+    fn keccakf(state: &mut [u64; 25]) {
+        lanes::keccakp1600(state, 24)
+    }
+}
+
+#[cfg(any(test, feature = "tiny-keccak"))]
+#[cfg_attr(feature = "keccak", allow(dead_code))]
+pub(crate) struct TinyKeccak;
+
+#[cfg(any(test, feature = "tiny-keccak"))]
+impl Permutation for TinyKeccak {
+    #[inline]
+    fn keccakf(state: &mut [u64; 25]) {
+        ::tiny_keccak::keccakf(state)
+    }
+
+    #[inline]
+    fn keccakp12(state: &mut [u64; 25]) {
+        ::tiny_keccak::keccakf12(state)
+    }
+}
+
+#[cfg(feature = "keccak")]
+pub(crate) struct RustCrypto;
+
+#[cfg(feature = "keccak")]
+impl Permutation for RustCrypto {
+    #[inline]
+    fn keccakf(state: &mut [u64; 25]) {
+        ::keccak::f1600(state)
+    }
+
+    #[inline]
+    fn keccakp12(state: &mut [u64; 25]) {
+        ::keccak::p1600(state, 12)
+    }
+}
+
+#[cfg(feature = "keccak")]
+type Selected = RustCrypto;
+
+#[cfg(all(feature = "tiny-keccak", not(feature = "keccak")))]
+type Selected = TinyKeccak;
+
+#[cfg(not(any(feature = "keccak", feature = "tiny-keccak")))]
+type Selected = Builtin;
+
+/// `Keccak-p[1600]` with the selected backend, 24 rounds for `Keccak-f[1600]` or 12.
+#[inline]
+pub(crate) fn keccakp(state: &mut [u64; 25], rounds: usize) {
+    match rounds {
+        24 => Selected::keccakf(state),
+        12 => Selected::keccakp12(state),
+        _ => unreachable!("Keccak-p[1600] with {} rounds", rounds)
+    }
+}
+
+
+#[test]
+fn test_permutation_zero() {
+    // the first words of Keccak-f[1600] on the zero state
+    let mut state = [0; 25];
+    keccakp(&mut state, 24);
+    assert_eq!(state[..2], [0xf1258f7940e1dde7, 0x84d5ccf933c0478a]);
+}
+
+#[test]
+fn test_permutation_backends() {
+    fn check<P: Permutation>() {
+        let mut state = [0u64; 25];
+        for (i, word) in state.iter_mut().enumerate() {
+            *word = (i as u64 + 1).wrapping_mul(0x0123_4567_89ab_cdef);
+        }
+
+        for _ in 0..100 {
+            let mut expected = state;
+            Builtin::keccakf(&mut expected);
+            P::keccakf(&mut state);
+            assert_eq!(state, expected);
+
+            let mut expected = state;
+            Builtin::keccakp12(&mut expected);
+            P::keccakp12(&mut state);
+            assert_eq!(state, expected);
+        }
+    }
+
+    check::<TinyKeccak>();
+    #[cfg(feature = "keccak")] check::<RustCrypto>();
+}
+
@@ -0,0 +1,70 @@
+use ::sponge::Sponge;
+
+
+/// The `cSHAKE` state after `bytepad(encode_string(N) || encode_string(S), rate)`.
+///
+/// The constructors are `const fn`, so that for a constant `N` and `S` the state is computed at
+/// compile time into a `static`, and building the function from it is a copy of the state:
+///
+/// ```
+/// use sp800_185::{ Prefix, TupleHash };
+///
+/// static MY_APP: Prefix = Prefix::new_tuplehash256(b"MyApp");
+///
+/// let mut hasher = TupleHash::from_prefix(&MY_APP);
+/// # let mut buf = [0; 32];
+/// hasher.update(&[b"a", b"b"]);
+/// hasher.finalize(&mut buf);
+/// ```
+#[derive(Clone)]
+pub struct Prefix {
+    pub(crate) sponge: Sponge,
+    pub(crate) name: &'static [u8],
+    pub(crate) custom: &'static [u8]
+}
+
+impl Prefix {
+    #[inline]
+    pub const fn new_cshake128(name: &'static [u8], custom: &'static [u8]) -> Self {
+        Prefix { sponge: Sponge::new_cshake(168, name, custom), name, custom }
+    }
+
+    #[inline]
+    pub const fn new_cshake256(name: &'static [u8], custom: &'static [u8]) -> Self {
+        Prefix { sponge: Sponge::new_cshake(136, name, custom), name, custom }
+    }
+
+    /// For `KMac::from_prefix`, the key is absorbed there.
+    #[inline]
+    pub const fn new_kmac128(custom: &'static [u8]) -> Self {
+        Prefix::new_cshake128(b"KMAC", custom)
+    }
+
+    /// For `KMac::from_prefix`, the key is absorbed there.
+    #[inline]
+    pub const fn new_kmac256(custom: &'static [u8]) -> Self {
+        Prefix::new_cshake256(b"KMAC", custom)
+    }
+
+    #[inline]
+    pub const fn new_tuplehash128(custom: &'static [u8]) -> Self {
+        Prefix::new_cshake128(b"TupleHash", custom)
+    }
+
+    #[inline]
+    pub const fn new_tuplehash256(custom: &'static [u8]) -> Self {
+        Prefix::new_cshake256(b"TupleHash", custom)
+    }
+
+    /// The function name `N`.
+    #[inline]
+    pub fn name(&self) -> &'static [u8] {
+        self.name
+    }
+
+    /// The customization string `S`.
+    #[inline]
+    pub fn custom(&self) -> &'static [u8] {
+        self.custom
+    }
+}
@@ -0,0 +1,187 @@
+//! Encoding recorder.
+//!
+//! Records every segment a sponge absorbs, with the name of the encoding it comes from, so the
+//! exact input of `cSHAKE` can be diffed against other implementations.
+//!
+//! ```ignore
+//! let ((), recording) = recorder::record(|| {
+//!     let mut kmac = KMac::new_kmac128(key, custom);
+//!     kmac.update(data);
+//!     kmac.finalize(&mut tag);
+//! });
+//! println!("{}", recording);
+//! ```
+//!
+//! ```text
+//! left_encode(rate)  01 a8
+//! encode_string(N)   01 20 4b 4d 41 43
+//! encode_string(S)   01 00
+//! bytepad zero fill  00 00 00 ...
+//! ```
+
+#[cfg(feature = "recorder")]
+pub use self::imp::*;
+#[cfg(feature = "recorder")]
+pub(crate) use self::imp::Tracker;
+#[cfg(not(feature = "recorder"))]
+pub(crate) use self::noop::Tracker;
+
+
+#[cfg(feature = "recorder")]
+mod imp {
+    use std::fmt;
+    use std::cell::RefCell;
+
+
+    thread_local!{
+        static RECORDING: RefCell<Option<Vec<Segment>>> = const { RefCell::new(None) };
+    }
+
+    /// One absorbed segment.
+    #[derive(Clone, Debug, PartialEq, Eq)]
+    pub struct Segment {
+        pub label: &'static str,
+        pub bytes: Vec<u8>
+    }
+
+    /// The segments absorbed by every sponge on the current thread, in order.
+    ///
+    /// `Display` dumps them as annotated hex, one segment per line.
+    #[derive(Clone, Debug, Default, PartialEq, Eq)]
+    pub struct Recording(pub Vec<Segment>);
+
+    impl Recording {
+        #[inline]
+        pub fn segments(&self) -> &[Segment] {
+            &self.0
+        }
+    }
+
+    impl fmt::Display for Recording {
+        fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+            let width = self.0.iter()
+                .map(|seg| seg.label.len())
+                .max()
+                .unwrap_or(0);
+
+            for seg in &self.0 {
+                write!(f, "{:1$}", seg.label, width)?;
+                for (i, line) in seg.bytes.chunks(16).enumerate() {
+                    if i != 0 {
+                        write!(f, "\n{:1$}", "", width)?;
+                    }
+                    for b in line {
+                        write!(f, " {:02x}", b)?;
+                    }
+                }
+                writeln!(f)?;
+            }
+
+            Ok(())
+        }
+    }
+
+    /// Runs `f` and records the segments absorbed on the current thread meanwhile.
+    ///
+    /// Every sponge is recorded, so a closure that drives two hashers interleaves their segments.
+    pub fn record<R, F: FnOnce() -> R>(f: F) -> (R, Recording) {
+        struct Guard(Option<Option<Vec<Segment>>>);
+
+        impl Drop for Guard {
+            fn drop(&mut self) {
+                let prev = self.0.take().unwrap_or(None);
+                RECORDING.with(|r| *r.borrow_mut() = prev);
+            }
+        }
+
+        let prev = RECORDING.with(|r| r.replace(Some(Vec::new())));
+        let _guard = Guard(Some(prev));
+        let output = f();
+        let segments = RECORDING.with(|r| r.borrow_mut().take())
+            .unwrap_or_default();
+
+        (output, Recording(segments))
+    }
+
+    fn with<F: FnOnce(&mut Vec<Segment>)>(f: F) {
+        RECORDING.with(|r| if let Some(ref mut segments) = *r.borrow_mut() {
+            f(segments)
+        });
+    }
+
+    /// Keeps the block offset, which the sponge does not expose.
+    #[derive(Clone)]
+    pub(crate) struct Tracker {
+        rate: usize,
+        offset: usize
+    }
+
+    impl Tracker {
+        #[inline]
+        pub(crate) fn new(rate: usize) -> Self {
+            Tracker { rate, offset: 0 }
+        }
+
+        /// A tracker for a sponge imported at `offset`.
+        #[inline]
+        pub(crate) fn resume(rate: usize, offset: usize) -> Self {
+            Tracker { rate, offset }
+        }
+
+        pub(crate) fn absorb(&mut self, label: &'static str, buf: &[u8]) {
+            self.offset = (self.offset + buf.len()) % self.rate;
+            with(|segments| segments.push(Segment { label, bytes: buf.to_vec() }));
+        }
+
+        pub(crate) fn absorb_more(&mut self, buf: &[u8]) {
+            self.offset = (self.offset + buf.len()) % self.rate;
+            with(|segments| if let Some(seg) = segments.last_mut() {
+                seg.bytes.extend_from_slice(buf)
+            });
+        }
+
+        pub(crate) fn fill_block(&mut self) {
+            // the block is permuted even if it is already full
+            let len = self.rate - self.offset;
+            self.offset = 0;
+            with(|segments| segments.push(Segment { label: "bytepad zero fill", bytes: vec![0; len] }));
+        }
+
+        pub(crate) fn pad(&mut self, delim: u8) {
+            let mut bytes = vec![0; self.rate - self.offset];
+            bytes[0] ^= delim;
+            *bytes.last_mut().unwrap() ^= 0x80;
+            with(|segments| segments.push(Segment { label: "pad", bytes }));
+        }
+    }
+}
+
+#[cfg(not(feature = "recorder"))]
+mod noop {
+    #[derive(Clone)]
+    pub(crate) struct Tracker;
+
+    impl Tracker {
+        #[inline(always)]
+        pub(crate) fn new(_rate: usize) -> Self {
+            Tracker
+        }
+
+        #[inline(always)]
+        pub(crate) fn resume(_rate: usize, _offset: usize) -> Self {
+            Tracker
+        }
+
+        #[inline(always)]
+        pub(crate) fn absorb(&mut self, _label: &'static str, _buf: &[u8]) {}
+
+        #[inline(always)]
+        pub(crate) fn absorb_more(&mut self, _buf: &[u8]) {}
+
+        #[inline(always)]
+        pub(crate) fn fill_block(&mut self) {}
+
+        #[inline(always)]
+        pub(crate) fn pad(&mut self, _delim: u8) {}
+    }
+}
@@ -0,0 +1,123 @@
+//! Sampling from XOF output, for values derived from a seed as in ML-KEM and ML-DSA.
+//!
+//! The rejection samplers read as much output as they need, which depends on the output, and
+//! should only derive public values, as the matrix `A` or the challenge `c`. `sample_cbd` reads a
+//! fixed amount and does not branch on it, for secrets.
+
+use ::sponge::XofStream;
+
+
+impl XofStream {
+    /// An unbiased integer in `0..bound`.
+    ///
+    /// Each candidate is the next `ceil(log2(bound) / 8)` bytes, little-endian, masked to
+    /// `ceil(log2(bound))` bits, rejected if not below `bound`.
+    pub fn uniform(&mut self, bound: u64) -> u64 {
+        assert!(bound > 0, "bound must be positive");
+
+        let bits = 64 - (bound - 1).leading_zeros();
+        let mask = if bits == 64 { u64::MAX } else { (1 << bits) - 1 };
+        let len = bits.div_ceil(8) as usize;
+
+        loop {
+            let mut buf = [0; 8];
+            self.fill(&mut buf[..len]);
+            let x = u64::from_le_bytes(buf) & mask;
+            if x < bound {
+                return x;
+            }
+        }
+    }
+
+    /// Fills `out` with uniform integers mod `q` by rejection, `2 <= q < 2^24`.
+    ///
+    /// The output is read three bytes at a time. Below `2^12`, they hold two 12-bit
+    /// little-endian candidates, as `SampleNTT` in ML-KEM. Otherwise, they hold one 24-bit
+    /// candidate, as `RejNTTPoly` in ML-DSA. Candidates are masked to the bit length of `q`.
+    pub fn sample_mod_q(&mut self, q: u32, out: &mut [u32]) {
+        assert!((2..1 << 24).contains(&q), "q must be in 2..2^24");
+
+        let bits = 32 - (q - 1).leading_zeros();
+        let mask = (1 << bits) - 1;
+
+        let mut i = 0;
+        while i < out.len() {
+            let mut buf = [0; 4];
+            self.fill(&mut buf[..3]);
+            let c = u32::from_le_bytes(buf);
+
+            if bits <= 12 {
+                for &d in &[c & 0xfff, c >> 12] {
+                    let d = d & mask;
+                    if d < q && i < out.len() {
+                        out[i] = d;
+                        i += 1;
+                    }
+                }
+            } else {
+                let d = c & mask;
+                if d < q {
+                    out[i] = d;
+                    i += 1;
+                }
+            }
+        }
+    }
+
+    /// Fills `out` from the centered binomial distribution of parameter `eta`, `1 <= eta <= 16`.
+    ///
+    /// Each coefficient takes the next `2 * eta` bits, little-endian: the number of set bits
+    /// among the first `eta`, minus those among the last `eta`, as `SamplePolyCBD` in ML-KEM.
+    /// It reads `2 * eta * out.len()` bits, rounded up to a byte, and does not branch on them.
+    pub fn sample_cbd(&mut self, eta: u32, out: &mut [i32]) {
+        assert!((1..=16).contains(&eta), "eta must be in 1..=16");
+
+        let low = (1u64 << eta) - 1;
+        let mut acc = 0u64;
+        let mut nbits = 0;
+
+        for coeff in out.iter_mut() {
+            while nbits < 2 * eta {
+                let mut byte = [0];
+                self.fill(&mut byte);
+                acc |= u64::from(byte[0]) << nbits;
+                nbits += 8;
+            }
+
+            let x = (acc & low).count_ones() as i32;
+            let y = ((acc >> eta) & low).count_ones() as i32;
+            *coeff = x - y;
+            acc >>= 2 * eta;
+            nbits -= 2 * eta;
+        }
+    }
+
+    /// Fills `out` with `weight` coefficients of `1` or `-1` and zeros elsewhere, as `SampleInBall`
+    /// in ML-DSA, `out.len() <= 256` and `weight <= min(out.len(), 64)`.
+    ///
+    /// The signs are the first 8 bytes, then each position is a byte, rejected if too large.
+    pub fn sample_in_ball(&mut self, weight: usize, out: &mut [i8]) {
+        let n = out.len();
+        assert!(n <= 256, "at most 256 coefficients");
+        assert!(weight <= n && weight <= 64, "weight must be at most min(out.len(), 64)");
+
+        for c in out.iter_mut() {
+            *c = 0;
+        }
+
+        let mut signs = self.read_u64_le();
+        for i in n - weight..n {
+            let j = loop {
+                let mut byte = [0];
+                self.fill(&mut byte);
+                if usize::from(byte[0]) <= i {
+                    break usize::from(byte[0]);
+                }
+            };
+
+            out[i] = out[j];
+            out[j] = 1 - 2 * (signs & 1) as i8;
+            signs >>= 1;
+        }
+    }
+}
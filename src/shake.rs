@@ -0,0 +1,62 @@
+use core::mem;
+use ::sponge::{ Sponge, XofStream };
+
+
+/// The `SHAKE128` and `SHAKE256` extendable-output functions of FIPS 202.
+///
+/// `cSHAKE` with an empty function name and customization string, for protocols that are
+/// specified over plain `SHAKE`.
+#[derive(Clone)]
+pub struct Shake(Sponge);
+
+impl Shake {
+    #[inline]
+    pub fn new_shake128() -> Self {
+        Shake(Sponge::new(168, 0x1f))
+    }
+
+    #[inline]
+    pub fn new_shake256() -> Self {
+        Shake(Sponge::new(136, 0x1f))
+    }
+
+    #[inline]
+    pub fn update(&mut self, buf: &[u8]) {
+        self.0.update(buf)
+    }
+
+    #[inline]
+    pub fn finalize(self, buf: &mut [u8]) {
+        self.0.finalize(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        let fresh = self.0.fresh();
+        mem::replace(&mut self.0, fresh).finalize(buf)
+    }
+
+    /// Back to the state before any input.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.0 = self.0.fresh();
+    }
+
+    #[inline]
+    pub fn xof(self) -> XofStream {
+        self.0.xof()
+    }
+}
+
+
+#[test]
+fn test_shake() {
+    // FIPS 202 example values
+    let mut buf = [0; 16];
+    Shake::new_shake128().finalize(&mut buf);
+    assert_eq!(buf, *b"\x7f\x9c\x2b\xa4\xe8\x8f\x82\x7d\x61\x60\x45\x50\x76\x05\x85\x3e");
+
+    Shake::new_shake256().finalize(&mut buf);
+    assert_eq!(buf, *b"\x46\xb9\xdd\x2b\x0b\xa8\x8d\x13\x23\x3b\x3f\xeb\x74\x3e\xeb\x24");
+}
@@ -0,0 +1,541 @@
+//! The Keccak sponge behind `CShake`, and its squeezing half, `XofStream`.
+
+use core::{ cmp, ops };
+use ::permutation::keccakp;
+use ::lanes::RC;
+use ::utils::left_encode;
+use ::midstate::{ MidstateError, SPONGE_LEN };
+#[cfg(feature = "alloc")] use alloc::vec::Vec;
+#[cfg(feature = "std")] use std::io;
+
+
+/// Largest rate in bytes, `cSHAKE128`.
+const MAX_RATE: usize = 168;
+
+
+#[derive(Clone)]
+pub(crate) struct Sponge {
+    state: [u64; 25],
+    rate: usize,
+    offset: usize,
+    delim: u8,
+    rounds: usize
+}
+
+impl Sponge {
+    #[inline]
+    pub(crate) fn new(rate: usize, delim: u8) -> Sponge {
+        assert!(rate != 0 && rate <= MAX_RATE && rate.is_multiple_of(8), "invalid rate");
+
+        Sponge { state: [0; 25], rate, offset: 0, delim, rounds: 24 }
+    }
+
+    /// The sponge of TurboSHAKE, on `Keccak-p[1600, 12]`.
+    #[inline]
+    pub(crate) fn new_turbo(rate: usize, delim: u8) -> Sponge {
+        Sponge { rounds: 12, ..Sponge::new(rate, delim) }
+    }
+
+    /// `cSHAKE` after `bytepad(encode_string(N) || encode_string(S), rate)`, in `const` context.
+    pub(crate) const fn new_cshake(rate: usize, name: &[u8], custom: &[u8]) -> Sponge {
+        let mut sponge = Sponge { state: [0; 25], rate, offset: 0, delim: 0x04, rounds: 24 };
+        let mut encbuf = [0; 9];
+
+        let pos = left_encode(&mut encbuf, rate as u64);
+        sponge.update_const(encbuf.split_at(pos).1);
+
+        let pos = left_encode(&mut encbuf, name.len() as u64 * 8);
+        sponge.update_const(encbuf.split_at(pos).1);
+        sponge.update_const(name);
+
+        let pos = left_encode(&mut encbuf, custom.len() as u64 * 8);
+        sponge.update_const(encbuf.split_at(pos).1);
+        sponge.update_const(custom);
+
+        sponge.state = keccakf_const(sponge.state);
+        sponge.offset = 0;
+        sponge
+    }
+
+    const fn update_const(&mut self, buf: &[u8]) {
+        let mut i = 0;
+        while i < buf.len() {
+            self.state[self.offset / 8] ^= (buf[i] as u64) << (8 * (self.offset % 8));
+            self.offset += 1;
+            i += 1;
+
+            if self.offset == self.rate {
+                self.state = keccakf_const(self.state);
+                self.offset = 0;
+            }
+        }
+    }
+
+    #[inline]
+    pub(crate) fn rate(&self) -> usize {
+        self.rate
+    }
+
+    pub(crate) fn update(&mut self, mut buf: &[u8]) {
+        while !buf.is_empty() {
+            let len = cmp::min(self.rate - self.offset, buf.len());
+            xorin(&mut self.state, self.offset, &buf[..len]);
+            self.offset += len;
+            buf = &buf[len..];
+
+            if self.offset == self.rate {
+                keccakp(&mut self.state, self.rounds);
+                self.offset = 0;
+            }
+        }
+    }
+
+    /// Permutes, and starts a new block, even if the block is empty.
+    #[inline]
+    pub(crate) fn fill_block(&mut self) {
+        keccakp(&mut self.state, self.rounds);
+        self.offset = 0;
+    }
+
+    #[inline]
+    pub(crate) fn finalize(self, buf: &mut [u8]) {
+        self.xof().fill(buf);
+    }
+
+    #[inline]
+    pub(crate) fn xof(self) -> XofStream {
+        let delim = self.delim;
+        self.xof_with_delim(delim)
+    }
+
+    /// Pads with `delim` instead of the one the sponge was created with.
+    pub(crate) fn xof_with_delim(mut self, delim: u8) -> XofStream {
+        self.pad(delim);
+
+        XofStream {
+            state: self.state,
+            rate: self.rate,
+            rounds: self.rounds,
+            offset: 0,
+            squeezed: 0
+        }
+    }
+
+    /// Pads the block with `delim` and `pad10*1`, and permutes.
+    #[inline]
+    pub(crate) fn pad(&mut self, delim: u8) {
+        xorin(&mut self.state, self.offset, &[delim]);
+        xorin(&mut self.state, self.rate - 1, &[0x80]);
+        self.fill_block();
+    }
+
+    /// Reads the rate, permuting before a block once the last one is used up.
+    pub(crate) fn squeeze(&mut self, mut buf: &mut [u8]) {
+        while !buf.is_empty() {
+            if self.offset == self.rate {
+                self.fill_block();
+            }
+
+            let len = cmp::min(self.rate - self.offset, buf.len());
+            let (head, tail) = buf.split_at_mut(len);
+            setout(&self.state, self.offset, head);
+            self.offset += len;
+            buf = tail;
+        }
+    }
+
+    /// Encrypts or decrypts in place with the rate as key stream, leaving the ciphertext in the
+    /// rate, as the duplex of SpongeWrap. Either way the plaintext is xored into the rate.
+    pub(crate) fn crypt(&mut self, mut buf: &mut [u8], decrypt: bool) {
+        while !buf.is_empty() {
+            let len = cmp::min(self.rate - self.offset, buf.len());
+            let (head, tail) = buf.split_at_mut(len);
+
+            if decrypt {
+                let mut stream = [0; MAX_RATE];
+                setout(&self.state, self.offset, &mut stream[..len]);
+                for (b, &s) in head.iter_mut().zip(stream.iter()) {
+                    *b ^= s;
+                }
+                xorin(&mut self.state, self.offset, head);
+            } else {
+                xorin(&mut self.state, self.offset, head);
+                setout(&self.state, self.offset, head);
+            }
+
+            self.offset += len;
+            buf = tail;
+
+            if self.offset == self.rate {
+                self.fill_block();
+            }
+        }
+    }
+
+    /// Zeroes the rate, so that earlier states cannot be recovered from later ones.
+    #[inline]
+    pub(crate) fn zero_rate(&mut self) {
+        for word in &mut self.state[..self.rate / 8] {
+            *word = 0;
+        }
+    }
+
+    /// A new sponge with the same rate, padding and rounds.
+    #[inline]
+    pub(crate) fn fresh(&self) -> Sponge {
+        Sponge { state: [0; 25], offset: 0, ..*self }
+    }
+
+    /// Absorb offset in the current block.
+    #[inline]
+    pub(crate) fn offset(&self) -> usize {
+        self.offset
+    }
+
+    /// Writes the sponge in the layout of `midstate`, `SPONGE_LEN` bytes.
+    pub(crate) fn export(&self, out: &mut [u8]) {
+        let (lanes, params) = out.split_at_mut(200);
+        for (chunk, word) in lanes.chunks_mut(8).zip(self.state.iter()) {
+            chunk.copy_from_slice(&word.to_le_bytes());
+        }
+        params.copy_from_slice(&[(self.rate / 8) as u8, self.offset as u8, self.delim, self.rounds as u8]);
+    }
+
+    /// Reads an exported sponge, which must pad with `delim` and permute with `rounds`.
+    pub(crate) fn import(buf: &[u8], delim: u8, rounds: usize) -> Result<Sponge, MidstateError> {
+        if buf.len() != SPONGE_LEN {
+            return Err(MidstateError::Length);
+        }
+
+        let (lanes, params) = buf.split_at(200);
+        let rate = usize::from(params[0]) * 8;
+        let offset = usize::from(params[1]);
+        if rate == 0 || rate > MAX_RATE || offset >= rate
+            || params[2] != delim || usize::from(params[3]) != rounds
+        {
+            return Err(MidstateError::Invalid);
+        }
+
+        let mut state = [0; 25];
+        for (word, chunk) in state.iter_mut().zip(lanes.chunks(8)) {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+            *word = u64::from_le_bytes(bytes);
+        }
+
+        Ok(Sponge { state, rate, offset, delim, rounds })
+    }
+}
+
+
+/// The output of an extendable-output function.
+///
+/// Returned by every `xof()`, it squeezes the sponge on demand, and keeps count of the bytes
+/// squeezed so far, skipped bytes included.
+#[derive(Clone)]
+pub struct XofStream {
+    state: [u64; 25],
+    rate: usize,
+    rounds: usize,
+    offset: usize,
+    squeezed: u64
+}
+
+impl XofStream {
+    pub fn fill(&mut self, mut buf: &mut [u8]) {
+        self.squeezed += buf.len() as u64;
+
+        while !buf.is_empty() {
+            if self.offset == self.rate {
+                keccakp(&mut self.state, self.rounds);
+                self.offset = 0;
+            }
+
+            let len = cmp::min(self.rate - self.offset, buf.len());
+            let (head, tail) = buf.split_at_mut(len);
+            setout(&self.state, self.offset, head);
+            self.offset += len;
+            buf = tail;
+        }
+    }
+
+    /// Same as `fill`, the name of `tiny_keccak::XofReader`.
+    #[inline]
+    pub fn squeeze(&mut self, buf: &mut [u8]) {
+        self.fill(buf)
+    }
+
+    /// Discards `n` bytes of output, permuting once per block without copying it out.
+    pub fn skip(&mut self, n: u64) {
+        self.squeezed += n;
+
+        let rest = (self.rate - self.offset) as u64;
+        if n <= rest {
+            self.offset += n as usize;
+            return;
+        }
+
+        let n = n - rest;
+        let rate = self.rate as u64;
+        for _ in 0..n.div_ceil(rate) {
+            keccakp(&mut self.state, self.rounds);
+        }
+        self.offset = (n - (n - 1) / rate * rate) as usize;
+    }
+
+    /// The next `n` bytes of output.
+    ///
+    /// With `io::Read` in scope, `stream.take(n)` is `Read::take`, call `XofStream::take` instead.
+    #[cfg(feature = "alloc")]
+    pub fn take(&mut self, n: usize) -> Vec<u8> {
+        let mut buf = core::iter::repeat_n(0, n).collect::<Vec<u8>>();
+        self.fill(&mut buf);
+        buf
+    }
+
+    #[inline]
+    pub fn read_u32_le(&mut self) -> u32 {
+        let mut buf = [0; 4];
+        self.fill(&mut buf);
+        u32::from_le_bytes(buf)
+    }
+
+    #[inline]
+    pub fn read_u64_le(&mut self) -> u64 {
+        let mut buf = [0; 8];
+        self.fill(&mut buf);
+        u64::from_le_bytes(buf)
+    }
+
+    /// Total bytes squeezed or skipped.
+    #[inline]
+    pub fn squeezed(&self) -> u64 {
+        self.squeezed
+    }
+
+    /// Rate of the sponge in bytes, the size of a block.
+    #[inline]
+    pub fn rate(&self) -> usize {
+        self.rate
+    }
+
+    /// An endless iterator over the output bytes.
+    #[inline]
+    pub fn bytes(&mut self) -> Bytes<'_> {
+        Bytes(self)
+    }
+
+    /// An endless iterator over the output, one block at a time.
+    ///
+    /// The first block is the rest of the current one, if some of it was already squeezed.
+    #[inline]
+    pub fn blocks(&mut self) -> Blocks<'_> {
+        Blocks(self)
+    }
+}
+
+#[cfg(feature = "std")]
+impl io::Read for XofStream {
+    #[inline]
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.fill(buf);
+        Ok(buf.len())
+    }
+}
+
+
+/// Iterator returned by `XofStream::bytes`.
+pub struct Bytes<'a>(&'a mut XofStream);
+
+impl<'a> Iterator for Bytes<'a> {
+    type Item = u8;
+
+    #[inline]
+    fn next(&mut self) -> Option<u8> {
+        let mut buf = [0];
+        self.0.fill(&mut buf);
+        Some(buf[0])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// Iterator returned by `XofStream::blocks`.
+pub struct Blocks<'a>(&'a mut XofStream);
+
+impl<'a> Iterator for Blocks<'a> {
+    type Item = Block;
+
+    fn next(&mut self) -> Option<Block> {
+        let stream = &mut *self.0;
+        if stream.offset == stream.rate {
+            keccakp(&mut stream.state, stream.rounds);
+            stream.offset = 0;
+        }
+
+        let mut block = Block { buf: [0; MAX_RATE], len: stream.rate - stream.offset };
+        stream.fill(&mut block.buf[..block.len]);
+        Some(block)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+/// One block of output, `rate` bytes at most.
+#[derive(Clone, Copy)]
+pub struct Block {
+    buf: [u8; MAX_RATE],
+    len: usize
+}
+
+impl ops::Deref for Block {
+    type Target = [u8];
+
+    #[inline]
+    fn deref(&self) -> &[u8] {
+        &self.buf[..self.len]
+    }
+}
+
+impl AsRef<[u8]> for Block {
+    #[inline]
+    fn as_ref(&self) -> &[u8] {
+        self
+    }
+}
+
+
+/// `Keccak-f[1600]` in `const` context.
+const fn keccakf_const(mut a: [u64; 25]) -> [u64; 25] {
+    const PI: [usize; 24] = [
+        10, 7, 11, 17, 18, 3, 5, 16, 8, 21, 24, 4, 15, 23, 19, 13, 12, 2, 20, 14, 22, 9, 6, 1
+    ];
+    const RHO: [u32; 24] = [
+        1, 3, 6, 10, 15, 21, 28, 36, 45, 55, 2, 14, 27, 41, 56, 8, 25, 43, 62, 18, 39, 61, 20, 44
+    ];
+
+    let mut round = 0;
+    while round < 24 {
+        // Theta
+        let mut c = [0; 5];
+        let mut x = 0;
+        while x < 5 {
+            c[x] = a[x] ^ a[x + 5] ^ a[x + 10] ^ a[x + 15] ^ a[x + 20];
+            x += 1;
+        }
+        let mut x = 0;
+        while x < 5 {
+            let d = c[(x + 4) % 5] ^ c[(x + 1) % 5].rotate_left(1);
+            let mut y = 0;
+            while y < 25 {
+                a[y + x] ^= d;
+                y += 5;
+            }
+            x += 1;
+        }
+
+        // Rho and pi
+        let mut last = a[1];
+        let mut i = 0;
+        while i < 24 {
+            let tmp = a[PI[i]];
+            a[PI[i]] = last.rotate_left(RHO[i]);
+            last = tmp;
+            i += 1;
+        }
+
+        // Chi
+        let mut y = 0;
+        while y < 25 {
+            let row = [a[y], a[y + 1], a[y + 2], a[y + 3], a[y + 4]];
+            let mut x = 0;
+            while x < 5 {
+                a[y + x] = row[x] ^ (!row[(x + 1) % 5] & row[(x + 2) % 5]);
+                x += 1;
+            }
+            y += 5;
+        }
+
+        // Iota
+        a[0] ^= RC[round];
+        round += 1;
+    }
+
+    a
+}
+
+fn xorin(state: &mut [u64; 25], mut offset: usize, mut buf: &[u8]) {
+    // bytes up to a word boundary, then whole words, the state is little-endian
+    while !offset.is_multiple_of(8) && !buf.is_empty() {
+        state[offset / 8] ^= u64::from(buf[0]) << (8 * (offset % 8));
+        offset += 1;
+        buf = &buf[1..];
+    }
+
+    let mut words = buf.chunks_exact(8);
+    for word in &mut words {
+        let mut bytes = [0; 8];
+        bytes.copy_from_slice(word);
+        state[offset / 8] ^= u64::from_le_bytes(bytes);
+        offset += 8;
+    }
+
+    for (i, &b) in words.remainder().iter().enumerate() {
+        state[offset / 8] ^= u64::from(b) << (8 * i);
+    }
+}
+
+fn setout(state: &[u64; 25], offset: usize, buf: &mut [u8]) {
+    for (i, b) in buf.iter_mut().enumerate() {
+        let pos = offset + i;
+        *b = (state[pos / 8] >> (8 * (pos % 8))) as u8;
+    }
+}
+
+
+#[test]
+fn test_keccakf_const() {
+    let mut state = [0; 25];
+    for (i, word) in state.iter_mut().enumerate() {
+        *word = (i as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    }
+
+    let expected = {
+        let mut state = state;
+        keccakp(&mut state, 24);
+        state
+    };
+    assert_eq!(keccakf_const(state), expected);
+}
+
+#[test]
+fn test_sponge_shake() {
+    use tiny_keccak::Keccak;
+
+    let mut input = [0; 1000];
+    for (i, b) in input.iter_mut().enumerate() {
+        *b = i as u8;
+    }
+    for &rate in &[168, 136] {
+        for &len in &[0, 1, 7, 8, 167, 168, 169, 500, 1000] {
+            let mut shake = Keccak::new(rate, 0x1f);
+            let mut sponge = Sponge::new(rate, 0x1f);
+            shake.update(&input[..len]);
+            for chunk in input[..len].chunks(13) {
+                sponge.update(chunk);
+            }
+
+            let mut expected = [0; 600];
+            let mut buf = [0; 600];
+            shake.finalize(&mut expected);
+            sponge.xof().fill(&mut buf);
+            assert_eq!(&buf[..], &expected[..]);
+        }
+    }
+}
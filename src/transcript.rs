@@ -0,0 +1,60 @@
+use ::cshake::CShake;
+use ::utils::left_encode;
+
+
+const APPEND_MESSAGE: u8 = 0x01;
+const CHALLENGE_BYTES: u8 = 0x02;
+
+
+/// Fiat–Shamir Transcript.
+///
+/// A `Transcript` is a `cSHAKE` instance with the function name `"Transcript"` and the protocol
+/// name as customization string. Every operation absorbs an operation code followed by
+/// `encode_string(label)`; messages are then framed as `encode_string(message)` and challenges
+/// as `left_encode(L)`, so that any sequence of operations is parsed unambiguously.
+///
+/// Challenges are squeezed from a fork of the state, and the main state keeps the challenge
+/// request, so later appends stay bound to every earlier challenge.
+/// A prover that needs to rewind can `clone` the transcript and restore the snapshot.
+#[derive(Clone)]
+pub struct Transcript(CShake);
+
+impl Transcript {
+    #[inline]
+    pub fn new_transcript128(protocol: &[u8]) -> Self {
+        Transcript(CShake::new_cshake128(b"Transcript", protocol))
+    }
+
+    #[inline]
+    pub fn new_transcript256(protocol: &[u8]) -> Self {
+        Transcript(CShake::new_cshake256(b"Transcript", protocol))
+    }
+
+    /// Back to the state after the protocol name, forgetting every operation.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
+    pub fn append_message(&mut self, label: &[u8], message: &[u8]) {
+        self.with_label(APPEND_MESSAGE, label);
+        self.0.absorb_string("encode_string(message)", message);
+    }
+
+    pub fn challenge_bytes(&mut self, label: &[u8], buf: &mut [u8]) {
+        let mut encbuf = [0; 9];
+
+        self.with_label(CHALLENGE_BYTES, label);
+
+        let pos = left_encode(&mut encbuf, buf.len() as u64 * 8);
+        self.0.absorb("left_encode(L)", &encbuf[pos..]);
+
+        self.0.clone().finalize(buf);
+    }
+
+    #[inline]
+    fn with_label(&mut self, op: u8, label: &[u8]) {
+        self.0.absorb("op", &[op]);
+        self.0.absorb_string("encode_string(label)", label);
+    }
+}
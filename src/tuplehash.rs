@@ -1,6 +1,9 @@
-use tiny_keccak::XofReader;
+use ::sponge::XofStream;
 use ::cshake::CShake;
+use ::prefix::Prefix;
 use ::utils::{ left_encode, right_encode };
+use ::element::TupleElement;
+use ::midstate::{ MidstateError, MIDSTATE_LEN, KIND_TUPLEHASH };
 
 
 /// Tuple Hash.
@@ -31,24 +34,60 @@ impl TupleHash {
         TupleHash(CShake::new_cshake256(b"TupleHash", custom))
     }
 
-    pub fn update<T: AsRef<[u8]>>(&mut self, input: &[T]) {
-        let mut encbuf = [0; 9];
+    /// `TupleHash` from `Prefix::new_tuplehash128` or `Prefix::new_tuplehash256`.
+    #[inline]
+    pub fn from_prefix(prefix: &Prefix) -> Self {
+        assert_eq!(prefix.name(), b"TupleHash", "not a TupleHash prefix");
+        TupleHash(CShake::from_prefix(prefix))
+    }
+
+    /// The state, to resume hashing with `import_midstate`.
+    #[inline]
+    pub fn export_midstate(&self) -> [u8; MIDSTATE_LEN] {
+        self.0.export_kind(KIND_TUPLEHASH)
+    }
+
+    #[inline]
+    pub fn import_midstate(buf: &[u8]) -> Result<Self, MidstateError> {
+        CShake::import_kind(buf, KIND_TUPLEHASH).map(TupleHash)
+    }
 
+    pub fn update<T: AsRef<[u8]>>(&mut self, input: &[T]) {
         for buf in input {
-            let buf = buf.as_ref();
-            // encode_string(X[i])
-            let pos = left_encode(&mut encbuf, buf.len() as u64 * 8);
-            self.0.update(&encbuf[pos..]);
-            self.0.update(buf);
+            self.0.absorb_string("encode_string(X[i])", buf.as_ref());
         }
     }
 
+    /// Absorbs one element in its canonical `TupleElement` encoding.
+    ///
+    /// `update_value(&42u32)` is the same as `update(&[b"\x04\x00\x00\x00\x2a"])`.
+    pub fn update_value<T: TupleElement + ?Sized>(&mut self, value: &T) {
+        let mut encbuf = [0; 9];
+
+        let pos = left_encode(&mut encbuf, value.encoded_len() as u64 * 8);
+        self.0.absorb("encode_string(X[i])", &encbuf[pos..]);
+        value.encode(&mut |buf| self.0.absorb_more(buf));
+    }
+
     #[inline]
     pub fn finalize(mut self, buf: &mut [u8]) {
         self.with_bitlength(buf.len() as u64 * 8);
         self.0.finalize(buf)
     }
 
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        self.with_bitlength(buf.len() as u64 * 8);
+        self.0.finalize_reset(buf)
+    }
+
+    /// Back to the state after the customization string.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.0.reset()
+    }
+
     /// A function on bit strings in which the output can be extended to  any desired length.
     ///
     /// Some applications of `TupleHash` may not know the number of output bits they will need until
@@ -56,7 +95,7 @@ impl TupleHash {
     /// XOF (i.e., the output can be extended to any desired length), which mimics the behavior of
     /// cSHAKE.
     #[inline]
-    pub fn xof(mut self) -> XofReader {
+    pub fn xof(mut self) -> XofStream {
         self.with_bitlength(0);
         self.0.xof()
     }
@@ -65,8 +104,7 @@ impl TupleHash {
     fn with_bitlength(&mut self, bitlength: u64) {
         let mut encbuf = [0; 9];
 
-        // right_encode(L)
         let pos = right_encode(&mut encbuf, bitlength);
-        self.0.update(&encbuf[pos..]);
+        self.0.absorb("right_encode(L)", &encbuf[pos..]);
     }
 }
@@ -0,0 +1,57 @@
+use core::mem;
+use ::sponge::{ Sponge, XofStream };
+
+
+/// The TurboSHAKE extendable-output functions of RFC 9861.
+///
+/// `SHAKE` on `Keccak-p[1600, 12]`, the last 12 rounds of the permutation, with a domain
+/// separation byte `D` in `0x01..=0x7f` in place of the `SHAKE` suffix. `TurboSHAKE128` and
+/// `TurboSHAKE256` have the rates of `SHAKE128` and `SHAKE256`.
+#[derive(Clone)]
+pub struct TurboShake(Sponge);
+
+impl TurboShake {
+    #[inline]
+    pub fn new_turboshake128(domain: u8) -> Self {
+        TurboShake::new(168, domain)
+    }
+
+    #[inline]
+    pub fn new_turboshake256(domain: u8) -> Self {
+        TurboShake::new(136, domain)
+    }
+
+    fn new(rate: usize, domain: u8) -> Self {
+        assert!((0x01..=0x7f).contains(&domain), "domain separation byte must be in 0x01..=0x7f");
+
+        TurboShake(Sponge::new_turbo(rate, domain))
+    }
+
+    #[inline]
+    pub fn update(&mut self, buf: &[u8]) {
+        self.0.update(buf)
+    }
+
+    #[inline]
+    pub fn finalize(self, buf: &mut [u8]) {
+        self.0.finalize(buf)
+    }
+
+    /// `finalize`, then `reset`.
+    #[inline]
+    pub fn finalize_reset(&mut self, buf: &mut [u8]) {
+        let fresh = self.0.fresh();
+        mem::replace(&mut self.0, fresh).finalize(buf)
+    }
+
+    /// Back to the state before any input.
+    #[inline]
+    pub fn reset(&mut self) {
+        self.0 = self.0.fresh();
+    }
+
+    #[inline]
+    pub fn xof(self) -> XofStream {
+        self.0.xof()
+    }
+}
@@ -1,43 +1,83 @@
-use byteorder::{ ByteOrder, BigEndian };
-
-
 /// `left_encode(x)` encodes the integer x as a byte string in a way that can be unambiguously parsed
 /// from the beginning of the string by inserting the length of the byte string before the byte string
 /// representation of x.
-pub fn left_encode(buf: &mut [u8; 9], value: u64) -> usize {
+pub const fn left_encode(buf: &mut [u8; 9], value: u64) -> usize {
     // ref https://cryptologie.net/article/388/shake-cshake-and-some-more-bit-ordering/
 
-    buf.copy_from_slice(&[0; 9]);
+    let bytes = value.to_be_bytes();
+    let offset = significant(&bytes);
 
-    BigEndian::write_u64(&mut buf[1..], value.to_le());
-    let offset = buf.iter()
-        .enumerate()
-        .find(|&(_, &v)| v != 0)
-        .map(|(n, _)| n)
-        .unwrap_or(8);
+    *buf = [0; 9];
+    let mut i = offset;
+    while i < 8 {
+        buf[i + 1] = bytes[i];
+        i += 1;
+    }
 
-    buf[offset - 1] = (9 - offset) as u8;
-    offset - 1
+    buf[offset] = (8 - offset) as u8;
+    offset
 }
 
 
 /// `right_encode(x)` encodes the integer x as a byte string in a way that can be unambiguously parsed
 /// from the end of the string by inserting the length of the byte string after the byte string
 /// representation of x.
-pub fn right_encode(buf: &mut [u8; 9], value: u64) -> usize {
-    buf.copy_from_slice(&[0; 9]);
+pub const fn right_encode(buf: &mut [u8; 9], value: u64) -> usize {
+    let bytes = value.to_be_bytes();
+    let offset = significant(&bytes);
 
-    BigEndian::write_u64(&mut buf[..8], value.to_le());
-    let offset = buf.iter()
-        .enumerate()
-        .find(|&(_, &v)| v != 0)
-        .map(|(n, _)| n)
-        .unwrap_or(7);
+    *buf = [0; 9];
+    let mut i = offset;
+    while i < 8 {
+        buf[i] = bytes[i];
+        i += 1;
+    }
 
     buf[8] = (8 - offset) as u8;
     offset
 }
 
+/// Index of the first significant byte, keeping at least one.
+const fn significant(bytes: &[u8; 8]) -> usize {
+    let mut offset = 0;
+    while offset < 7 && bytes[offset] == 0 {
+        offset += 1;
+    }
+    offset
+}
+
+/// Shortest tag the `verify` methods accept, shorter tags never match.
+pub const MIN_TAG_LEN: usize = 4;
+
+/// Squeezes `tag.len()` bytes with `squeeze`, and compares them with `tag` in constant time.
+///
+/// Tags shorter than `MIN_TAG_LEN`, the empty tag included, are rejected without squeezing.
+pub(crate) fn verify_tag<F: FnMut(&mut [u8])>(tag: &[u8], mut squeeze: F) -> bool {
+    if tag.len() < MIN_TAG_LEN {
+        return false;
+    }
+
+    let mut encbuf = [0; 64];
+    let mut acc = true;
+
+    for chunk in tag.chunks(encbuf.len()) {
+        let buf = &mut encbuf[..chunk.len()];
+        squeeze(buf);
+        acc &= ct_eq(buf, chunk);
+    }
+
+    acc
+}
+
+/// Compares two equal-length byte strings without branching on their contents.
+pub(crate) fn ct_eq(a: &[u8], b: &[u8]) -> bool {
+    debug_assert_eq!(a.len(), b.len());
+
+    a.iter()
+        .zip(b)
+        .fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
 
 #[test]
 fn test_left_encode() {
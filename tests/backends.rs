@@ -0,0 +1,118 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, KMac, TupleHash, FixedParallelHash };
+
+
+// Known answers over several permutations, to run with each backend feature: `cargo test`,
+// then with `--features tiny-keccak` and `--features keccak`. The expected values were computed
+// by a plain Python `Keccak-f[1600]`, checked against the SHAKE of hashlib and the SP800-185
+// samples.
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+fn data(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 7 + 3) as u8).collect()
+}
+
+/// The first and last 32 bytes of a long output.
+fn ends(buf: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    (buf[..32].to_vec(), buf[buf.len() - 32..].to_vec())
+}
+
+#[test]
+fn test_backend_cshake() {
+    let mut buf = [0; 600];
+
+    let mut cshake = CShake::new_cshake128(b"Backend", b"cross-check");
+    cshake.update(&data(1000));
+    cshake.finalize(&mut buf);
+    assert_eq!(ends(&buf), (
+        hex("a5b550ec382a320282f20eee7937c285495ae799c979a9e1028b1514e063a1b2"),
+        hex("f366ee1c7c7b4c180031e94ba85bb8d88da6d1422ecb5bfff2ef780f412e6e9f")
+    ));
+
+    let mut cshake = CShake::new_cshake256(b"Backend", b"cross-check");
+    cshake.update(&data(1000));
+    cshake.finalize(&mut buf);
+    assert_eq!(ends(&buf), (
+        hex("98b715b5685e2e9857a215aab265f14f444487495bed6bc508f83c11eba24cf5"),
+        hex("55cf0eb0fca16a96fae8cd88dc9eccfe48661cd88ab7ec63062db7ce08dd5959")
+    ));
+}
+
+#[test]
+fn test_backend_kmac() {
+    let mut buf = [0; 600];
+
+    let mut kmac = KMac::new_kmac128(&data(100), b"cross-check");
+    kmac.update(&data(1000));
+    kmac.finalize(&mut buf);
+    assert_eq!(ends(&buf), (
+        hex("755be517a31f63179ac39eedd9f86bf2ee2bc2d89c7ec896bbe9509206d91315"),
+        hex("9c3ad3e1d3bda3893d11980b1a673f1801654a0410ecd5a43d7055266972c3af")
+    ));
+
+    let mut kmac = KMac::new_kmac256(&data(100), b"cross-check");
+    kmac.update(&data(1000));
+    kmac.finalize(&mut buf);
+    assert_eq!(ends(&buf), (
+        hex("3ce77237aa8fee1ba7c578e1f301c6fb04b4cf0d6930359692f192732dac29c7"),
+        hex("7bc990fc3c5da773d4c125fd84a4541872566ca0039f9dfca51f29394e7f1b8d")
+    ));
+}
+
+#[test]
+fn test_backend_tuplehash() {
+    let mut buf = [0; 64];
+    let input = [data(0), data(300), data(5)];
+
+    let mut tuplehash = TupleHash::new_tuplehash128(b"cross-check");
+    tuplehash.update(&input);
+    tuplehash.finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("502de4b7f5a5fc75600afbfe382d420f7e7da686c6b45600b87454db6b14db2e\
+        d849db2de76bbd4a319df01374b91b762fd697413d8ff68093cbd02a179f8034"));
+
+    let mut tuplehash = TupleHash::new_tuplehash256(b"cross-check");
+    tuplehash.update(&input);
+    tuplehash.finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("9783d9c20b525f5ff913c14912f7c4b25e6b0c8434dc3eb9f35eb4f4373253fe\
+        79bbabf5d8a82c8f3a832f24d636688651cb0e8633f977102e12a61b343b7fa2"));
+}
+
+#[test]
+fn test_backend_parallelhash() {
+    let mut buf = [0; 64];
+    let input = data(2049);
+
+    let mut hasher = FixedParallelHash::<100>::new_parallelhash128(b"cross-check");
+    hasher.update(&input);
+    hasher.finalize(&mut buf);
+    let expected128 = hex("5dc17141cd7c079ec4f2e34b4694fa7988808336cb478027ebd8bf7a15aee249\
+        e8b3c133ddabcf6df98bd2bfbfcd3977cbc58d1daa3f4f3b26a94f06246b4d06");
+    assert_eq!(buf.to_vec(), expected128);
+
+    let mut hasher = FixedParallelHash::<200>::new_parallelhash256(b"cross-check");
+    hasher.update(&input);
+    hasher.finalize(&mut buf);
+    let expected256 = hex("83c55c1826e19d09cedd132af572784bdbf1021cb8d410bd5047d09360afd6e9\
+        77820175d26abf7e185c714afd930fa9d10df85c414250a840294619e371af74");
+    assert_eq!(buf.to_vec(), expected256);
+
+    #[cfg(feature = "alloc")] {
+        use sp800_185::ParallelHash;
+
+        let mut hasher = ParallelHash::new_parallelhash128(b"cross-check", 100);
+        hasher.update(&input);
+        hasher.finalize(&mut buf);
+        assert_eq!(buf.to_vec(), expected128);
+
+        let mut hasher = ParallelHash::new_parallelhash256(b"cross-check", 200);
+        hasher.update(&input);
+        hasher.finalize(&mut buf);
+        assert_eq!(buf.to_vec(), expected256);
+    }
+}
@@ -0,0 +1,119 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, Duplex };
+use sp800_185::utils::MIN_TAG_LEN;
+
+
+#[test]
+fn test_duplex_cshake() {
+    let input = (0..500u32).map(|i| i as u8).collect::<Vec<u8>>();
+
+    for &len in &[0, 1, 167, 168, 169, 500] {
+        let mut expected = [0; 400];
+        let mut cshake = CShake::new_cshake128(b"Duplex", b"test");
+        cshake.update(&input[..len]);
+        cshake.finalize(&mut expected);
+
+        let mut duplex = Duplex::new_duplex128(b"Duplex", b"test");
+        for chunk in input[..len].chunks(100) {
+            duplex.absorb(chunk);
+        }
+        let mut buf = [0; 400];
+        duplex.squeeze(&mut buf[..10]);
+        duplex.squeeze(&mut buf[10..]);
+        assert_eq!(&buf[..], &expected[..]);
+    }
+}
+
+#[test]
+fn test_duplex_phases() {
+    fn output(f: &dyn Fn(&mut Duplex)) -> [u8; 32] {
+        let mut duplex = Duplex::new_duplex256(b"", b"phases");
+        f(&mut duplex);
+        let mut buf = [0; 32];
+        duplex.squeeze(&mut buf);
+        buf
+    }
+
+    let mut scratch = [0; 16];
+    let a = output(&|d| d.absorb(b"ab"));
+    assert_eq!(a, output(&|d| { d.absorb(b"a"); d.absorb(b"b") }));
+    assert_ne!(a, output(&|d| { d.absorb(b"a"); d.squeeze(&mut [0; 16]); d.absorb(b"b") }));
+    assert_ne!(a, output(&|d| { d.absorb(b"a"); d.encrypt(&mut [b'b']) }));
+    assert_ne!(a, output(&|d| { d.absorb(b"ab"); d.ratchet() }));
+    assert_ne!(output(&|d| d.encrypt(&mut [])), output(&|_| ()));
+
+    // squeezing continues the output
+    let mut duplex = Duplex::new_duplex256(b"", b"phases");
+    duplex.absorb(b"ab");
+    duplex.squeeze(&mut scratch);
+    assert_eq!(scratch, a[..16]);
+    duplex.squeeze(&mut scratch);
+    assert_eq!(scratch, a[16..]);
+}
+
+#[test]
+fn test_duplex_aead() {
+    fn seal(key: &[u8], nonce: &[u8], ad: &[u8], buf: &mut [u8], tag: &mut [u8]) {
+        let mut duplex = Duplex::new_duplex128(b"", b"aead");
+        duplex.absorb(key);
+        duplex.absorb(nonce);
+        duplex.ratchet();
+        duplex.absorb(ad);
+        duplex.encrypt(buf);
+        duplex.squeeze(tag);
+    }
+
+    fn open(key: &[u8], nonce: &[u8], ad: &[u8], buf: &mut [u8], tag: &[u8]) -> bool {
+        let mut duplex = Duplex::new_duplex128(b"", b"aead");
+        duplex.absorb(key);
+        duplex.absorb(nonce);
+        duplex.ratchet();
+        duplex.absorb(ad);
+        duplex.decrypt(buf);
+        duplex.verify(tag)
+    }
+
+    let key = [0x42; 32];
+    let nonce = [0x24; 16];
+    let plaintext = (0..1000u32).map(|i| (i * 7) as u8).collect::<Vec<u8>>();
+
+    let mut buf = plaintext.clone();
+    let mut tag = [0; 32];
+    seal(&key, &nonce, b"header", &mut buf, &mut tag);
+    assert_ne!(buf, plaintext);
+
+    let mut opened = buf.clone();
+    assert!(open(&key, &nonce, b"header", &mut opened, &tag));
+    assert_eq!(opened, plaintext);
+
+    let mut tampered = buf.clone();
+    tampered[500] ^= 1;
+    assert!(!open(&key, &nonce, b"header", &mut tampered, &tag));
+
+    let mut opened = buf.clone();
+    assert!(!open(&key, &nonce, b"footer", &mut opened, &tag));
+
+    // the key stream depends on the plaintext before it
+    let mut other = plaintext.clone();
+    other[0] ^= 1;
+    seal(&key, &nonce, b"header", &mut other, &mut tag);
+    assert_eq!(other[0], buf[0] ^ 1);
+    assert_ne!(other[168..], buf[168..]);
+}
+
+
+#[test]
+fn test_duplex_verify_short_tag() {
+    let mut duplex = Duplex::new_duplex128(b"", b"");
+    duplex.absorb(b"message");
+    let mut tag = [0; 16];
+    duplex.clone().squeeze(&mut tag);
+
+    assert!(duplex.clone().verify(&tag));
+    assert!(duplex.clone().verify(&tag[..MIN_TAG_LEN]));
+
+    // empty and short tags never match
+    assert!(!duplex.clone().verify(&[]));
+    assert!(!duplex.clone().verify(&tag[..MIN_TAG_LEN - 1]));
+}
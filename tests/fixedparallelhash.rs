@@ -0,0 +1,58 @@
+extern crate sp800_185;
+
+use sp800_185::FixedParallelHash;
+
+
+#[test]
+fn test_fixedparallelhash() {
+    let x192 = b"\x00\x01\x02\x03\x04\x05\x06\x07\x10\x11\x12\x13\x14\x15\x16\x17\x20\x21\x22\x23\x24\x25\x26\x27";
+
+    let output = b"\xFC\x48\x4D\xCB\x3F\x84\xDC\xEE\xDC\x35\x34\x38\x15\x1B\xEE\x58\x15\x7D\x6E\xFE\xD0\x44\x5A\x81\xF1\x65\xE4\x95\x79\x5B\x72\x06";
+    let mut buf = [0; 32];
+    let mut hasher = FixedParallelHash::<8>::new_parallelhash128(b"Parallel Data");
+    hasher.update(&x192[..13]);
+    hasher.update(&x192[13..]);
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf, output);
+
+    let output = b"\xC1\x0A\x05\x27\x22\x61\x46\x84\x14\x4D\x28\x47\x48\x50\xB4\x10\x75\x7E\x3C\xBA\x87\x65\x1B\xA1\x67\xA5\xCB\xDD\xFF\x7F\x46\x66\
+                        \x75\xFB\xF8\x4B\xCA\xE7\x37\x8A\xC4\x44\xBE\x68\x1D\x72\x94\x99\xAF\xCA\x66\x7F\xB8\x79\x34\x8B\xFD\xDA\x42\x78\x63\xC8\x2F\x1C";
+    let mut buf = [0; 64];
+    let mut hasher = FixedParallelHash::<8>::new_parallelhash256(b"");
+    hasher.update(x192);
+    let mut xof = hasher.xof();
+    xof.squeeze(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_fixedparallelhash_dynamic() {
+    use sp800_185::ParallelHash;
+
+    let input = (0..10007u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut output = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", 100);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    let mut buf = [0; 64];
+    let mut hasher = FixedParallelHash::<100>::new_parallelhash256(b"Parallel Data");
+    for chunk in input.chunks(333) {
+        hasher.update(chunk);
+    }
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+
+    let mut output = [0; 32];
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 1);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    let mut buf = [0; 32];
+    let mut hasher = FixedParallelHash::<1>::new_parallelhash128(b"");
+    hasher.update(&input);
+    hasher.finalize(&mut buf);
+    assert_eq!(buf, output);
+}
@@ -0,0 +1,132 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, KMac, TupleHash };
+use sp800_185::function::{ FunctionBuilder, Output, Encoding, BuildError, RESERVED_NAMES };
+
+
+#[test]
+fn test_function_builder() {
+    for &name in &[&b"KMAC"[..], b"TupleHash", b"ParallelHash", b"Midstate", b"HashId checksum"] {
+        let err = FunctionBuilder::new_function128(name).build().err();
+        assert_eq!(err, Some(BuildError::ReservedName));
+    }
+    for &name in RESERVED_NAMES {
+        assert!(FunctionBuilder::new_function256(name).build().is_err());
+        assert!(FunctionBuilder::new_function256(name).allow_reserved().build().is_ok());
+    }
+
+    // the empty name is cSHAKE, and SHAKE with an empty customization string
+    let err = FunctionBuilder::new_function128(b"").with_custom(b"custom").build().err();
+    assert_eq!(err, Some(BuildError::ReservedName));
+    let mut expected = [0; 32];
+    let mut buf = [0; 32];
+    let mut cshake = CShake::new_cshake128(b"", b"custom");
+    cshake.update(b"ab");
+    cshake.finalize(&mut expected);
+    let mut function = FunctionBuilder::new_function128(b"")
+        .with_custom(b"custom")
+        .allow_reserved()
+        .build()
+        .unwrap();
+    function.update(b"ab");
+    function.finalize(&mut buf);
+    assert_eq!(buf, expected);
+
+    // the defaults are cSHAKE
+    let mut expected = [0; 64];
+    let mut buf = [0; 64];
+    let mut cshake = CShake::new_cshake256(b"MyFunction", b"custom");
+    cshake.update(b"ab");
+    cshake.finalize(&mut expected);
+
+    let mut function = FunctionBuilder::new_function256(b"MyFunction")
+        .with_custom(b"custom")
+        .build()
+        .unwrap();
+    function.update(b"a");
+    function.update(b"b");
+    function.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+
+    // reserved names reproduce the functions they belong to
+    let mut tuplehash = TupleHash::new_tuplehash128(b"custom");
+    tuplehash.update(&[&b"a"[..], b"bc"]);
+    tuplehash.finalize(&mut expected);
+
+    let mut function = FunctionBuilder::new_function128(b"TupleHash")
+        .with_custom(b"custom")
+        .with_output(Output::BoundLength)
+        .with_encoding(Encoding::Strings)
+        .allow_reserved()
+        .build()
+        .unwrap();
+    function.update(b"a");
+    function.update(b"bc");
+    function.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+
+    // a KMAC with an empty key is a prefix away
+    let mut kmac = KMac::new_kmac128(b"", b"");
+    kmac.update(b"msg");
+    let mut function = FunctionBuilder::new_function128(b"KMAC")
+        .with_output(Output::BoundLength)
+        .allow_reserved()
+        .build()
+        .unwrap();
+    function.update(&[0x01, 0xa8, 0x01, 0x00]);
+    function.update(&[0; 164]);
+    function.update(b"msg");
+    kmac.finalize(&mut expected);
+    function.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+}
+
+#[test]
+fn test_function_output() {
+    let builder = FunctionBuilder::new_function128(b"MyFunction")
+        .with_output(Output::BoundLength);
+
+    let mut short = [0; 16];
+    let mut long = [0; 32];
+    builder.build().unwrap().finalize(&mut short);
+    builder.build().unwrap().finalize(&mut long);
+    assert_ne!(short[..], long[..16]);
+
+    let mut xof = [0; 32];
+    builder.build().unwrap().xof().fill(&mut xof);
+    assert_ne!(xof[..], long[..]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_function_registry() {
+    use sp800_185::function::Registry;
+
+    let mut registry = Registry::new();
+    let a = FunctionBuilder::new_function128(b"MyFunction").with_custom(b"a");
+    let b = FunctionBuilder::new_function256(b"MyFunction").with_custom(b"b");
+    registry.build(&a).unwrap();
+    registry.build(&b).unwrap();
+    assert_eq!(registry.len(), 2);
+
+    // same rate, name and customization string, other settings
+    let c = FunctionBuilder::new_function128(b"MyFunction")
+        .with_custom(b"a")
+        .with_output(Output::BoundLength);
+    assert_eq!(registry.build(&c).err(), Some(BuildError::Collision));
+
+    // the rate is part of the domain
+    let d = FunctionBuilder::new_function128(b"MyFunction").with_custom(b"b");
+    registry.build(&d).unwrap();
+    assert_eq!(registry.len(), 3);
+
+    // nothing is registered for a reserved or empty name
+    assert_eq!(registry.build(&FunctionBuilder::new_function128(b"KMAC")).err(), Some(BuildError::ReservedName));
+    assert_eq!(registry.register(&FunctionBuilder::new_function128(b"Midstate")).err(), Some(BuildError::ReservedName));
+    assert_eq!(registry.register(&FunctionBuilder::new_function256(b"")).err(), Some(BuildError::ReservedName));
+    assert_eq!(registry.len(), 3);
+
+    let reserved = FunctionBuilder::new_function128(b"KMAC").allow_reserved();
+    registry.register(&reserved).unwrap();
+    assert_eq!(registry.len(), 4);
+}
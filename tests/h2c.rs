@@ -0,0 +1,139 @@
+extern crate sp800_185;
+
+use sp800_185::{ Shake, CShake };
+use sp800_185::h2c::{ expand_message_xof, hash_to_field };
+
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+// The RFC 9380 vectors below were recomputed from sections 5.2 and 5.3 with the SHAKE of
+// Python's hashlib, for the messages, DSTs and lengths of the RFC.
+
+fn messages() -> (Vec<u8>, Vec<u8>) {
+    let q128 = [&b"q128_"[..], &[b'q'; 128]].concat();
+    let a512 = [&b"a512_"[..], &[b'a'; 512]].concat();
+    (q128, a512)
+}
+
+#[test]
+fn test_expand_message_xof() {
+    let (q128, a512) = messages();
+
+    // RFC 9380, appendix K, expand_message_xof(SHAKE128)
+    let dst = b"QUUX-V01-CS02-with-expander-SHAKE128";
+    let shake = Shake::new_shake128();
+    let vectors: [(&[u8], &str, &str); 5] = [
+        (b"", "86518c9cd86581486e9485aa74ab35ba150d1c75c88e26b7043e44e2acd735a2",
+            "7314ff1a155a2fb99a0171dc71b89ab6e3b2b7d59e38e64419b8b6294d03ffee42491f11370261f436220ef787f8f76f\
+            5b26bdcd850071920ce023f3ac46847744f4612b8714db8f5db83205b2e625d95afd7d7b4d3094d3bdde815f52850bb4\
+            1ead9822e08f22cf41d615a303b0d9dde73263c049a7b9898208003a739a2e57"),
+        (b"abc", "8696af52a4d862417c0763556073f47bc9b9ba43c99b505305cb1ec04a9ab468",
+            "c952f0c8e529ca8824acc6a4cab0e782fc3648c563ddb00da7399f2ae35654f4860ec671db2356ba7baa55a34a9d7f79\
+            197b60ddae6e64768a37d699a78323496db3878c8d64d909d0f8a7de4927dcab0d3dbbc26cb20a49eceb0530b431cdf4\
+            7bc8c0fa3e0d88f53b318b6739fbed7d7634974f1b5c386d6230c76260d5337a"),
+        (b"abcdef0123456789", "912c58deac4821c3509dbefa094df54b34b8f5d01a191d1d3108a2c89077acca",
+            "19b65ee7afec6ac06a144f2d6134f08eeec185f1a890fe34e68f0e377b7d0312883c048d9b8a1d6ecc3b541cb4987c26\
+            f45e0c82691ea299b5e6889bbfe589153016d8131717ba26f07c3c14ffbef1f3eff9752e5b6183f43871a78219a75e70\
+            00fbac6a7072e2b83c790a3a5aecd9d14be79f9fd4fb180960a3772e08680495"),
+        (&q128, "1adbcc448aef2a0cebc71dac9f756b22e51839d348e031e63b33ebb50faeaf3f",
+            "ca1b56861482b16eae0f4a26212112362fcc2d76dcc80c93c4182ed66c5113fe41733ed68be2942a3487394317f33798\
+            56f4822a611735e50528a60e7ade8ec8c71670fec6661e2c59a09ed36386513221688b35dc47e3c3111ee8c67ff49579\
+            089d661caa29db1ef10eb6eace575bf3dc9806e7c4016bd50f3c0e2a6481ee6d"),
+        (&a512, "df3447cc5f3e9a77da10f819218ddf31342c310778e0e4ef72bbaecee786a4fe",
+            "9d763a5ce58f65c91531b4100c7266d479a5d9777ba761693d052acd37d149e7ac91c796a10b919cd74a591a1e38719f\
+            b91b7203e2af31eac3bff7ead2c195af7d88b8bc0a8adf3d1e90ab9bed6ddc2b7f655dd86c730bdeaea884e737410971\
+            42c92f0e3fc1811b699ba593c7fbd81da288a29d423df831652e3a01a9374999")
+    ];
+    for &(msg, short, long) in &vectors {
+        let mut out = [0; 0x20];
+        expand_message_xof(&shake, msg, dst, 128, &mut out);
+        assert_eq!(out.to_vec(), hex(short));
+        let mut out = [0; 0x80];
+        expand_message_xof(&shake, msg, dst, 128, &mut out);
+        assert_eq!(out.to_vec(), hex(long));
+    }
+
+    // RFC 9380, appendix K, expand_message_xof(SHAKE256)
+    let dst = b"QUUX-V01-CS02-with-expander-SHAKE256";
+    let shake = Shake::new_shake256();
+    let vectors: [(&[u8], &str, &str); 5] = [
+        (b"", "2ffc05c48ed32b95d72e807f6eab9f7530dd1c2f013914c8fed38c5ccc15ad76",
+            "7a1361d2d7d82d79e035b8880c5a3c86c5afa719478c007d96e6c88737a3f631dd74a2c88df79a4cb5e5d9f7504957c7\
+            0d669ec6bfedc31e01e2bacc4ff3fdf9b6a00b17cc18d9d72ace7d6b81c2e481b4f73f34f9a7505dccbe8f5485f3d20c\
+            5409b0310093d5d6492dea4e18aa6979c23c8ea5de01582e9689612afbb353df"),
+        (b"abc", "b39e493867e2767216792abce1f2676c197c0692aed061560ead251821808e07",
+            "a54303e6b172909783353ab05ef08dd435a558c3197db0c132134649708e0b9b4e34fb99b92a9e9e28fc1f1d8860d858\
+            97a8e021e6382f3eea10577f968ff6df6c45fe624ce65ca25932f679a42a404bc3681efe03fcd45ef73bb3a8f79ba784\
+            f80f55ea8a3c367408f30381299617f50c8cf8fbb21d0f1e1d70b0131a7b6fbe"),
+        (b"abcdef0123456789", "245389cf44a13f0e70af8665fe5337ec2dcd138890bb7901c4ad9cfceb054b65",
+            "e42e4d9538a189316e3154b821c1bafb390f78b2f010ea404e6ac063deb8c0852fcd412e098e231e43427bd2be1330bb\
+            47b4039ad57b30ae1fc94e34993b162ff4d695e42d59d9777ea18d3848d9d336c25d2acb93adcad009bcfb9cde12286d\
+            f267ada283063de0bb1505565b2eb6c90e31c48798ecdc71a71756a9110ff373"),
+        (&q128, "719b3911821e6428a5ed9b8e600f2866bcf23c8f0515e52d6c6c019a03f16f0e",
+            "4ac054dda0a38a65d0ecf7afd3c2812300027c8789655e47aecf1ecc1a2426b17444c7482c99e5907afd9c25b9919904\
+            90bb9c686f43e79b4471a23a703d4b02f23c669737a886a7ec28bddb92c3a98de63ebf878aa363a501a60055c048bea1\
+            1840c4717beae7eee28c3cfa42857b3d130188571943a7bd747de831bd6444e0"),
+        (&a512, "9181ead5220b1963f1b5951f35547a5ea86a820562287d6ca4723633d17ccbbc",
+            "09afc76d51c2cccbc129c2315df66c2be7295a231203b8ab2dd7f95c2772c68e500bc72e20c602abc9964663b7a03a38\
+            9be128c56971ce81001a0b875e7fd17822db9d69792ddf6a23a151bf470079c518279aef3e75611f8f828994a9988f4a\
+            8a256ddb8bae161e658d5a2a09bcfe839c6396dc06ee5c8ff3c22d3b1f9deb7e")
+    ];
+    for &(msg, short, long) in &vectors {
+        let mut out = [0; 0x20];
+        expand_message_xof(&shake, msg, dst, 256, &mut out);
+        assert_eq!(out.to_vec(), hex(short));
+        let mut out = [0; 0x80];
+        expand_message_xof(&shake, msg, dst, 256, &mut out);
+        assert_eq!(out.to_vec(), hex(long));
+    }
+
+    // oversized DST
+    let mut out = [0; 32];
+    let long = [&b"long-"[..], &[b'D'; 300]].concat();
+    expand_message_xof(&Shake::new_shake128(), b"abc", &long, 128, &mut out);
+    assert_eq!(out.to_vec(), hex("01cd4c9b9b6c93acc98faa90e0c68b8c333abb5ce351f3212ff8bd22b6b06cf1"));
+
+    // cSHAKE as H
+    expand_message_xof(&CShake::new_cshake128(b"", b"h2c"), b"abc", b"DST", 128, &mut out);
+    assert_eq!(out.to_vec(), hex("09a09a00ea3182ccd642df3a594d8f660f25fa1581cc06f43f669ff91758fcc3"));
+}
+
+#[test]
+fn test_hash_to_field() {
+    let (q128, a512) = messages();
+
+    // RFC 9380, appendix J, u0 and u1 of edwards448_XOF:SHAKE256_ELL2_RO_
+    let p448 = hex("fffffffffffffffffffffffffffffffffffffffffffffffffffffffeffffffffffffffffffffffffffffffffffffffffffffffffffffffff");
+    let dst = b"QUUX-V01-CS02-with-edwards448_XOF:SHAKE256_ELL2_RO_";
+    let vectors: [(&[u8], &str, &str); 5] = [
+        (b"",
+            "0847c5ebf957d3370b1f98fde499fb3e659996d9fc9b5707176ade785ba72cd84b8a5597c12b1024be5f510fa5ba99642c4cec7f3f69d3e7",
+            "f8cbd8a7ae8c8deed071f3ac4b93e7cfcb8f1eac1645d699fd6d3881cb295a5d3006d9449ed7cad412a77a1fe61e84a9e41d59ef384d6f9a"),
+        (b"abc",
+            "04d975cd938ab49be3e81703d6a57cca84ed80d2ff6d4756d3f22947fb5b70ab0231f0087cbfb4b7cae73b41b0c9396b356a4831d9a14322",
+            "2547ca887ac3db7b5fad3a098aa476e90078afe1358af6c63d677d6edfd2100bc004e0f5db94dd2560fc5b308e223241d00488c9ca6b0ef2"),
+        (b"abcdef0123456789",
+            "10659ce25588db4e4be6f7c791a79eb21a7f24aaaca76a6ca3b83b80aaf95aa328fe7d569a1ac99f9cd216edf3915d72632f1a8b990e250c",
+            "9243e5b6c480683fd533e81f4a778349a309ce00bd163a29eb9fa8dbc8f549242bef33e030db21cffacd408d2c4264b93e476c6a8590e7aa"),
+        (&q128,
+            "c80390020e578f009ead417029eff6cd0926110922db63ab98395e3bdfdd5d8a65b1a2b8d495dc8c5e59b7f3518731f7dfc0f93ace5dee4b",
+            "1c4dc6653a445bbef2add81d8e90a6c8591a788deb91d0d3f1519a2e4a460313041b77c1b0817f2e80b388e5c3e49f37d787dc1f85e4324a"),
+        (&a512,
+            "163c79ab0210a4b5e4f44fb19437ea965bf5431ab233ef16606f0b03c5f16a3feb7d46a5a675ce8f606e9c2bf74ee5336c54a1e54919f13f",
+            "f99666bde4995c4088333d6c2734687e815f80a99c6da02c47df4b51f6c9d9ed466b4fecf7d9884990a8e0d0be6907fa437e0b1a27f49265")
+    ];
+    for &(msg, u0, u1) in &vectors {
+        let mut out = [0; 2 * 56];
+        hash_to_field(&Shake::new_shake256(), msg, dst, 224, &p448, &mut out);
+        assert_eq!(out[..56].to_vec(), hex(u0));
+        assert_eq!(out[56..].to_vec(), hex(u1));
+    }
+
+    let mut out = [0; 8];
+    hash_to_field(&CShake::new_cshake128(b"", b"h2c"), b"", b"DST", 128, &[0x0d, 0x01], &mut out);
+    assert_eq!(out.to_vec(), hex("0c8f07e40b240af6"));
+}
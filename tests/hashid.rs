@@ -0,0 +1,75 @@
+extern crate sp800_185;
+
+use sp800_185::hashid::{ hash_id, uuid_v8 };
+
+
+#[test]
+fn test_hash_id() {
+    let output = b"\x44\x62\x33\x73\x6D\x93\xC3\xC4\xCD\xBF\x7E\x76\x97\x84\x08\x69";
+    let mut buf = [0; 16];
+    hash_id(b"example.com", b"alice", &mut buf);
+    assert_eq!(&buf, output);
+
+    // the output length is absorbed
+    let output = b"\xA7\x07\x95\xCA\x9B\xC4\xB0\x89\x5A\xFC\x42\xD6\x06\x81\x0F\x80\xD4\xB7\xBB\x38\xDD\x6B\xAE\x0F\x14\xD4\xB3\xFB\x40\xAE\xFD\x0E";
+    let mut buf = [0; 32];
+    hash_id(b"example.com", b"alice", &mut buf);
+    assert_eq!(&buf, output);
+
+    let output = b"\xA2\xCF\x8D\xD5\x9E\x3B\x23\x52";
+    let mut buf = [0; 8];
+    hash_id(b"", b"", &mut buf);
+    assert_eq!(&buf, output);
+}
+
+#[test]
+fn test_uuid_v8() {
+    let output = b"\x44\x62\x33\x73\x6D\x93\x83\xC4\x8D\xBF\x7E\x76\x97\x84\x08\x69";
+    let uuid = uuid_v8(b"example.com", b"alice");
+    assert_eq!(&uuid, output);
+    assert_eq!(uuid[6] >> 4, 8);
+    assert_eq!(uuid[8] >> 6, 0b10);
+
+    let output = b"\x49\x83\x23\xF9\x6F\x19\x86\x04\x9D\x5D\xE3\x62\x3C\x34\x12\x18";
+    let uuid = uuid_v8(b"example.org", b"alice");
+    assert_eq!(&uuid, output);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_hash_id_text() {
+    use sp800_185::hashid::{
+        encode_base32, decode_base32,
+        encode_base58, decode_base58,
+        DecodeError
+    };
+
+    let uuid = uuid_v8(b"example.com", b"alice");
+
+    let text = encode_base32(&uuid);
+    assert_eq!(text, "8HH36WVDJE1W93DZFSV9F108D5CPP43D");
+    assert_eq!(decode_base32(&text).unwrap(), uuid);
+    assert_eq!(decode_base32("8hh36wvdje1w93dzfsv9f108d5cpp43d").unwrap(), uuid);
+    assert_eq!(decode_base32("8HH36WVDJEIW93DZFSV9FIO8D5CPP43D").unwrap(), uuid);
+    assert_eq!(decode_base32("8HH36WVDJE1W93DZFSV9F108D5CPP43E"), Err(DecodeError::InvalidChecksum));
+    assert_eq!(decode_base32("8HH36WVDJE1W93DZFSV9F108D5CPP4"), Err(DecodeError::InvalidLength));
+    assert_eq!(decode_base32("8HH36WVDJE1W93DZFSV9F108D5CPP43U"), Err(DecodeError::InvalidCharacter));
+
+    // the checksum is not `hash_id` in the namespace "checksum"
+    assert_eq!(decode_base32("8HH36WVDJE1W93DZFSV9F108D5Z4GGKV"), Err(DecodeError::InvalidChecksum));
+
+    let text = encode_base58(&uuid);
+    assert_eq!(text, "xFsuDkbM2eg5Pho2pv72zdbCzBE");
+    assert_eq!(decode_base58(&text).unwrap(), uuid);
+    assert_eq!(decode_base58("xFsuDkbM2eg5Pho2pv72zdbCzBF"), Err(DecodeError::InvalidChecksum));
+    assert_eq!(decode_base58("xFsuDkbM2eg5Pho2pv72zdbCzB0"), Err(DecodeError::InvalidCharacter));
+    assert_eq!(decode_base58("1"), Err(DecodeError::InvalidLength));
+
+    // leading zero bytes
+    let text = encode_base58(&[0, 0, 1]);
+    assert_eq!(text, "11Ap9btA");
+    assert_eq!(decode_base58(&text).unwrap(), [0, 0, 1]);
+
+    assert_eq!(decode_base32(&encode_base32(b"")).unwrap(), b"");
+    assert_eq!(decode_base58(&encode_base58(b"")).unwrap(), b"");
+}
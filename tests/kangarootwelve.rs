@@ -0,0 +1,180 @@
+extern crate sp800_185;
+
+use sp800_185::{ TurboShake, KangarooTwelve };
+
+
+fn hex(s: &str) -> Vec<u8> {
+    (0..s.len()).step_by(2)
+        .map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+        .collect()
+}
+
+/// `ptn(n)`, the repeated pattern `00 01 .. FA` of RFC 9861.
+fn ptn(n: usize) -> Vec<u8> {
+    (0..n).map(|i| (i % 251) as u8).collect()
+}
+
+fn kt128(msg: &[u8], custom: &[u8], len: usize) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    let mut hasher = KangarooTwelve::new_kt128(custom);
+    hasher.update(msg);
+    hasher.finalize(&mut buf);
+    buf
+}
+
+fn kt256(msg: &[u8], custom: &[u8], len: usize) -> Vec<u8> {
+    let mut buf = vec![0; len];
+    let mut hasher = KangarooTwelve::new_kt256(custom);
+    hasher.update(msg);
+    hasher.finalize(&mut buf);
+    buf
+}
+
+#[test]
+fn test_turboshake() {
+    // RFC 9861
+    let mut buf = [0; 32];
+    TurboShake::new_turboshake128(0x1f).finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("1e415f1c5983aff2169217277d17bb538cd945a397ddec541f1ce41af2c1b74c"));
+
+    let mut hasher = TurboShake::new_turboshake128(0x1f);
+    hasher.update(&ptn(17));
+    hasher.finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("9c97d036a3bac819db70ede0ca554ec6e4c2a1a4ffbfd9ec269ca6a111161233"));
+
+    let mut hasher = TurboShake::new_turboshake128(0x06);
+    hasher.update(&[0xff; 3]);
+    hasher.finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("3d03988bb59e681851a192f429ae03988e8f444bc06036a3f1a7d2ccd758d174"));
+
+    let mut buf = [0; 64];
+    TurboShake::new_turboshake256(0x1f).finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("367a329dafea871c7802ec67f905ae13c57695dc2c6663c61035f59a18f8e7db\
+        11edc0e12e91ea60eb6b32df06dd7f002fbafabb6e13ec1cc20d995547600db0"));
+
+    // TurboSHAKE256 on ptn(17^i), recomputed with a Python Keccak-p[1600, 12]
+    let vectors = [
+        "3e1712f928f8eaf1054632b2aa0a246ed8b0c378728f60bc970410155c28820e\
+            90cc90d8a3006aa2372c5c5ea176b0682bf22bae7467ac94f74d43d39b0482e2",
+        "b3bab0300e6a191fbe6137939835923578794ea54843f5011090fa2f3780a9e5\
+            cb22c59d78b40a0fbff9e672c0fbe0970bd2c845091c6044d687054da5d8e9c7",
+        "66b810db8e90780424c0847372fdc95710882fde31c6df75beb9d4cd9305cfca\
+            e35e7b83e8b7e6eb4b78605880116316fe2c078a09b94ad7b8213c0a738b65c0",
+        "c74ebc919a5b3b0dd1228185ba02d29ef442d69d3d4276a93efe0bf9a16a7dc0\
+            cd4eabadab8cd7a5edd96695f5d360abe09e2c6511a3ec397da3b76b9e1674fb",
+        "02cc3a8897e6f4f6ccb6fd46631b1f5207b66c6de9c7b55b2d1a23134a170afd\
+            ac234eaba9a77cff88c1f020b73724618c5687b362c430b248cd38647f848a1d",
+        "add53b06543e584b5823f626996aee50fe45ed15f20243a7165485acb4aa76b4\
+            ffda75cedf6d8cdc95c332bd56f4b986b58bb17d1778bfc1b1a97545cdf4ec9f"
+    ];
+    for (i, expected) in vectors.iter().enumerate() {
+        let mut hasher = TurboShake::new_turboshake256(0x1f);
+        hasher.update(&ptn(17usize.pow(i as u32)));
+        hasher.finalize(&mut buf);
+        assert_eq!(buf.to_vec(), hex(expected));
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_turboshake_domain() {
+    TurboShake::new_turboshake128(0x80);
+}
+
+#[test]
+fn test_kangarootwelve() {
+    // RFC 9861
+    assert_eq!(kt128(b"", b"", 32), hex("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5"));
+    assert_eq!(kt128(b"", b"", 64), hex("1ac2d450fc3b4205d19da7bfca1b37513c0803577ac7167f06fe2ce1f0ef39e5\
+        4269c056b8c82e48276038b6d292966cc07a3d4645272e31ff38508139eb0a71"));
+    assert_eq!(kt128(b"", b"", 10032)[10000..], hex("e8dc563642f7228c84684c898405d3a834799158c079b12880277a1d28e2ff6d")[..]);
+
+    let vectors = [
+        "2bda92450e8b147f8a7cb629e784a058efca7cf7d8218e02d345dfaa65244a1f",
+        "6bf75fa2239198db4772e36478f8e19b0f371205f6a9a93a273f51df37122888",
+        "0c315ebcdedbf61426de7dcf8fb725d1e74675d7f5327a5067f367b108ecb67c",
+        "cb552e2ec77d9910701d578b457ddf772c12e322e4ee7fe417f92c758f0d59d0",
+        "8701045e22205345ff4dda05555cbb5c3af1a771c2b89baef37db43d9998b9fe",
+        "844d610933b1b9963cbdeb5ae3b6b05cc7cbd67ceedf883eb678a0a8e0371682"
+    ];
+    for (i, expected) in vectors.iter().enumerate() {
+        assert_eq!(kt128(&ptn(17usize.pow(i as u32)), b"", 32), hex(expected));
+    }
+
+    let vectors = [
+        "fab658db63e94a246188bf7af69a133045f46ee984c56e3c3328caaf1aa1a583",
+        "d848c5068ced736f4462159b9867fd4c20b808acc3d5bc48e0b06ba0a3762ec4",
+        "c389e5009ae57120854c2e8c64670ac01358cf4c1baf89447a724234dc7ced74",
+        "75d2f86a2e644566726b4fbcfc5657b9dbcf070c7b0dca06450ab291d7443bcf"
+    ];
+    for (i, expected) in vectors.iter().enumerate() {
+        let msg = vec![0xff; (1 << i) - 1];
+        assert_eq!(kt128(&msg, &ptn(41usize.pow(i as u32)), 32), hex(expected));
+    }
+
+    // around the chunk boundary
+    assert_eq!(kt128(&ptn(8191), b"", 32), hex("1b577636f723643e990cc7d6a659837436fd6a103626600eb8301cd1dbe553d6"));
+    assert_eq!(kt128(&ptn(8192), b"", 32), hex("48f256f6772f9edfb6a8b661ec92dc93b95ebd05a08a17b39ae3490870c926c3"));
+    assert_eq!(kt128(&ptn(8192), &ptn(8189), 32), hex("3ed12f70fb05ddb58689510ab3e4d23c6c6033849aa01e1d8c220a297fedcd0b"));
+
+    // KT256
+    let mut buf = [0; 64];
+    KangarooTwelve::new_kt256(b"").finalize(&mut buf);
+    assert_eq!(buf.to_vec(), hex("b23d2e9cea9f4904e02bec06817fc10ce38ce8e93ef4c89e6537076af8646404\
+        e3e8b68107b8833a5d30490aa33482353fd4adc7148ecb782855003aaebde4a9"));
+
+    // KT256 over one to many chunks, recomputed with a Python Keccak-p[1600, 12]
+    let vectors = [
+        "0d005a194085360217128cf17f91e1f71314efa5564539d444912e3437efa17f\
+            82db6f6ffe76e781eaa068bce01f2bbf81eacb983d7230f2fb02834a21b1ddd0",
+        "1ba3c02b1fc514474f06c8979978a9056c8483f4a1b63d0dccefe3a28a2f323e\
+            1cdcca40ebf006ac76ef0397152346837b1277d3e7faa9c9653b19075098527b",
+        "de8ccbc63e0f133ebb4416814d4c66f691bbf8b6a61ec0a7700f836b086cb029\
+            d54f12ac7159472c72db118c35b4e6aa213c6562caaa9dcc518959e69b10f3ba",
+        "647efb49fe9d717500171b41e7f11bd491544443209997ce1c2530d15eb1ffbb\
+            598935ef954528ffc152b1e4d731ee2683680674365cd191d562bae753b84aa5",
+        "b06275d284cd1cf205bcbe57dccd3ec1ff6686e3ed15776383e1f2fa3c6ac8f0\
+            8bf8a162829db1a44b2a43ff83dd89c3cf1ceb61ede659766d5ccf817a62ba8d",
+        "9473831d76a4c7bf77ace45b59f1458b1673d64bcd877a7c66b2664aa6dd149e\
+            60eab71b5c2bab858c074ded81ddce2b4022b5215935c0d4d19bf511aeeb0772"
+    ];
+    for (i, expected) in vectors.iter().enumerate() {
+        assert_eq!(kt256(&ptn(17usize.pow(i as u32)), b"", 64), hex(expected));
+    }
+
+    let vectors = [
+        "9280f5cc39b54a5a594ec63de0bb99371e4609d44bf845c2f5b8c316d72b1598\
+            11f748f23e3fabbe5c3226ec96c62186df2d33e9df74c5069ceecbb4dd10eff6",
+        "47ef96dd616f200937aa7847e34ec2feae8087e3761dc0f8c1a154f51dc9ccf8\
+            45d7adbce57ff64b639722c6a1672e3bf5372d87e00aff89be97240756998853",
+        "3b48667a5051c5966c53c5d42b95de451e05584e7806e2fb765eda959074172c\
+            b438a9e91dde337c98e9c41bed94c4e0aef431d0b64ef2324f7932caa6f54969",
+        "e0911cc00025e1540831e266d94add9b98712142b80d2629e643aac4efaf5a3a\
+            30a88cbf4ac2a91a2432743054fbcc9897670e86ba8cec2fc2ace9c966369724"
+    ];
+    for (i, expected) in vectors.iter().enumerate() {
+        let msg = vec![0xff; (1 << i) - 1];
+        assert_eq!(kt256(&msg, &ptn(41usize.pow(i as u32)), 64), hex(expected));
+    }
+
+    assert_eq!(kt256(&ptn(8192), b"", 64), hex("c6ee8e2ad3200c018ac87aaa031cdac22121b412d07dc6e0dccbb53423747e9a\
+        1c18834d99df596cf0cf4b8dfafb7bf02d139d0c9035725adc1a01b7230a41fa"));
+    assert_eq!(kt256(&ptn(8192), &ptn(8189), 64), hex("74e47879f10a9c5d11bd2da7e194fe57e86378bf3c3f7448eff3c576a0f18c5c\
+        aae0999979512090a7f348af4260d4de3c37f1ecaf8d2c2c96c1d16c64b12496"));
+}
+
+#[test]
+fn test_kangarootwelve_incremental() {
+    let msg = ptn(17usize.pow(4));
+    let expected = kt128(&msg, b"custom", 32);
+
+    for &size in &[1, 100, 8191, 8192, 8193, 20000] {
+        let mut hasher = KangarooTwelve::new_kt128(b"custom");
+        for chunk in msg.chunks(size) {
+            hasher.update(chunk);
+        }
+        let mut buf = [0; 32];
+        hasher.finalize(&mut buf);
+        assert_eq!(buf.to_vec(), expected);
+    }
+}
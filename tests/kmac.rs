@@ -1,6 +1,7 @@
 extern crate sp800_185;
 
 use sp800_185::KMac;
+use sp800_185::utils::MIN_TAG_LEN;
 
 
 #[test]
@@ -202,3 +203,114 @@ fn test_kmac256_xof() {
     xof.squeeze(&mut buf);
     assert_eq!(buf, &output[..]);
 }
+
+
+// Keyed TupleHash: KMAC over the `TupleHash` element encoding, using the
+// `TupleHash` sample tuples with the `KMAC` sample key. The outputs are
+// computed from this implementation, they are not NIST vectors.
+
+#[test]
+fn test_kmac128_tuple() {
+    let key = b"\x40\x41\x42\x43\x44\x45\x46\x47\x48\x49\x4A\x4B\x4C\x4D\x4E\x4F\x50\x51\x52\x53\x54\x55\x56\x57\x58\x59\x5A\x5B\x5C\x5D\x5E\x5F";
+    let te3 = b"\x00\x01\x02";
+    let te6 = b"\x10\x11\x12\x13\x14\x15";
+    let te9 = b"\x20\x21\x22\x23\x24\x25\x26\x27\x28";
+    let s0 = b"";
+    let s1 = b"My Tuple App";
+
+
+    let output = b"\x01\xA2\x72\x8B\x89\xAE\x0B\xFE\x85\x88\x82\x5D\x01\x92\x96\x4A\x04\x40\x0B\x19\xE4\xE7\x5B\xE4\x73\x43\x92\x4C\x4E\xE4\xE2\x5D";
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac128(key, s0);
+    kmac.update_tuple(&[&te3[..], &te6[..]]);
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, output);
+
+    // same as framing the elements by hand
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac128(key, s0);
+    kmac.update(b"\x01\x18\x00\x01\x02\x01\x30\x10\x11\x12\x13\x14\x15");
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, output);
+
+
+    let output = b"\x7D\xC3\x00\x32\xBC\xD8\xD2\xA2\x37\x0D\x88\x32\xBF\xB6\x7F\xB9\x02\x02\x8B\xEE\x48\x11\xDD\x4A\x58\x4B\x11\x0F\x87\x24\x03\x8F";
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac128(key, s1);
+    kmac.update_tuple(&[&te3[..], &te6[..], &te9[..]]);
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, output);
+
+
+    let output = b"\x84\xB2\xFF\x40\x3F\x3D\x81\x63\xBA\x1E\x9C\xA1\x6A\x94\x6B\x73\xD0\x1F\x4E\x64\x5F\x77\xDC\xB1\xE7\xD2\xBC\x1E\x84\x6F\xFF\x53";
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac128(key, s1);
+    kmac.update_tuple(&[&te3[..], &te6[..], &te9[..]]);
+    kmac.xof().squeeze(&mut buf);
+    assert_eq!(buf, output);
+}
+
+#[test]
+fn test_kmac256_tuple() {
+    let key = b"\x40\x41\x42\x43\x44\x45\x46\x47\x48\x49\x4A\x4B\x4C\x4D\x4E\x4F\x50\x51\x52\x53\x54\x55\x56\x57\x58\x59\x5A\x5B\x5C\x5D\x5E\x5F";
+    let te3 = b"\x00\x01\x02";
+    let te6 = b"\x10\x11\x12\x13\x14\x15";
+    let te9 = b"\x20\x21\x22\x23\x24\x25\x26\x27\x28";
+    let s1 = b"My Tuple App";
+
+
+    let output = b"\x94\x6B\x84\x21\x1E\x0B\xB4\x71\x22\x37\x4F\x9C\x93\x7A\xB2\x81\x75\x3C\xB0\x6B\x01\x67\xB9\xDA\xDD\x51\x99\x94\xF6\xDC\x68\x11\
+                    \x46\x6F\x3D\x9C\x8C\x96\xCB\x8D\xD8\x16\x77\xD2\x3F\xAC\x71\x5E\xF1\xCF\xE1\xDE\xC6\x5C\x63\x94\xAC\x95\x19\x51\xAF\x47\x3B\x83";
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac256(key, s1);
+    kmac.update_tuple(&[&te3[..], &te6[..]]);
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, &output[..]);
+
+
+    let output = b"\xEF\x6E\xC0\x57\xDF\xB7\xA6\xE6\x52\x81\x67\xA7\x28\xC7\xC7\xC7\x61\x1C\xD4\xC1\xCC\xCB\x75\x10\x42\x66\x7C\x61\x14\x77\x8F\x3E\
+                    \x0E\x2E\xB9\xC2\x43\x69\x82\x02\xDC\x18\x85\xD1\x8A\x41\x99\x2A\xA2\xF8\x2A\x45\x52\x5B\x11\x0F\x88\x78\xE0\x0C\xB5\x40\x28\x9C";
+    let mut buf = vec![0; output.len()];
+    let mut kmac = KMac::new_kmac256(key, s1);
+    kmac.update_tuple(&[&te3[..], &te6[..], &te9[..]]);
+    kmac.xof().squeeze(&mut buf);
+    assert_eq!(buf, &output[..]);
+}
+
+#[test]
+fn test_kmac_verify() {
+    let key = b"\x40\x41\x42\x43\x44\x45\x46\x47\x48\x49\x4A\x4B\x4C\x4D\x4E\x4F\x50\x51\x52\x53\x54\x55\x56\x57\x58\x59\x5A\x5B\x5C\x5D\x5E\x5F";
+    let te3 = b"\x00\x01\x02";
+    let te6 = b"\x10\x11\x12\x13\x14\x15";
+    let s1 = b"My Tuple App";
+
+    let mut tag = [0; 64];
+    let mut kmac = KMac::new_kmac256(key, s1);
+    kmac.update_tuple(&[&te3[..], &te6[..]]);
+    kmac.clone().finalize(&mut tag);
+    assert!(kmac.clone().verify(&tag));
+
+    // truncated tags are unrelated
+    assert!(!kmac.clone().verify(&tag[..32]));
+
+    let mut bad = tag;
+    bad[63] ^= 1;
+    assert!(!kmac.clone().verify(&bad));
+
+    let mut tag = [0; 100];
+    kmac.clone().xof().squeeze(&mut tag);
+    assert!(kmac.clone().verify_xof(&tag));
+    assert!(kmac.clone().verify_xof(&tag[..32]));
+    tag[99] ^= 1;
+    assert!(!kmac.clone().verify_xof(&tag));
+
+    // empty and short tags never match
+    assert!(!kmac.clone().verify(&[]));
+    assert!(!kmac.clone().verify_xof(&[]));
+    assert!(!kmac.clone().verify_xof(&tag[..MIN_TAG_LEN - 1]));
+    assert!(kmac.clone().verify_xof(&tag[..MIN_TAG_LEN]));
+
+    let mut short = [0; MIN_TAG_LEN - 1];
+    kmac.clone().finalize(&mut short);
+    assert!(!kmac.verify(&short));
+}
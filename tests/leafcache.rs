@@ -0,0 +1,150 @@
+#![cfg(feature = "std")]
+
+extern crate sp800_185;
+
+use std::fs;
+use std::io::{ self, Cursor };
+use sp800_185::{ LeafCache, ParallelHash };
+use sp800_185::hashid::hash_id;
+
+
+fn parallelhash256(data: &[u8]) -> [u8; 64] {
+    let mut buf = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"disk", 1000);
+    hasher.update(data);
+    hasher.finalize(&mut buf);
+    buf
+}
+
+#[test]
+fn test_leafcache() {
+    let mut data = (0..10500u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+    let mut buf = [0; 64];
+
+    let mut cache = LeafCache::new_leafcache256(b"disk", 1000);
+    cache.set_len(data.len() as u64);
+    assert_eq!(cache.dirty_leaves(), 11);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+    assert_eq!(cache.dirty_leaves(), 0);
+
+    // inside one leaf
+    data[1500..1600].copy_from_slice(&[0xff; 100]);
+    cache.update_range(1500, &data[1500..1600]);
+    assert_eq!(cache.dirty_leaves(), 1);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+
+    // covers leaf 3, overlaps 2 and 4
+    data[2900..4100].copy_from_slice(&[0xee; 1200]);
+    cache.update_range(2900, &data[2900..4100]);
+    assert_eq!(cache.dirty_leaves(), 2);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+
+    // grows the partial last leaf, and adds a new one
+    data.extend_from_slice(&[0xdd; 800]);
+    cache.update_range(10500, &data[10500..]);
+    assert_eq!(cache.dirty_leaves(), 1);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+
+    // truncated
+    data.truncate(5500);
+    cache.set_len(5500);
+    assert_eq!(cache.dirty_leaves(), 1);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+
+    data.truncate(0);
+    cache.set_len(0);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+}
+
+#[test]
+fn test_leafcache_persist() {
+    let dir = std::env::temp_dir();
+    let path = dir.join(format!("sp800-185-leafcache-{}", std::process::id()));
+    let cache_path = dir.join(format!("sp800-185-leafcache-{}.cache", std::process::id()));
+
+    let data = (0..4321u32).map(|i| (i * 13 + 1) as u8).collect::<Vec<u8>>();
+    fs::write(&path, &data).unwrap();
+    let metadata = fs::metadata(&path).unwrap();
+
+    let mut buf = [0; 64];
+    let mut cache = LeafCache::new_leafcache256(b"disk", 1000);
+    cache.set_len(data.len() as u64);
+    cache.digest(fs::File::open(&path).unwrap(), &mut buf).unwrap();
+    cache.update_range(100, &data[100..200]);
+    cache.save(fs::File::create(&cache_path).unwrap(), &metadata).unwrap();
+
+    let mut loaded = LeafCache::load(fs::File::open(&cache_path).unwrap(), &metadata).unwrap();
+    assert_eq!(loaded.len(), data.len() as u64);
+    assert_eq!(loaded.dirty_leaves(), 1);
+    loaded.digest(fs::File::open(&path).unwrap(), &mut buf).unwrap();
+    assert_eq!(&buf[..], &parallelhash256(&data)[..]);
+
+    // corrupted
+    let mut bytes = fs::read(&cache_path).unwrap();
+    bytes[40] ^= 1;
+    let err = LeafCache::load(&bytes[..], &metadata).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    // the file changed behind the cache's back
+    fs::write(&path, &data[..4000]).unwrap();
+    let metadata = fs::metadata(&path).unwrap();
+    let err = LeafCache::load(fs::File::open(&cache_path).unwrap(), &metadata).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+
+    fs::remove_file(&path).unwrap();
+    fs::remove_file(&cache_path).unwrap();
+}
+
+#[test]
+fn test_leafcache_bad_timestamp() {
+    let path = std::env::temp_dir().join(format!("sp800-185-leafcache-time-{}", std::process::id()));
+    fs::write(&path, [0x5a; 100]).unwrap();
+    let metadata = fs::metadata(&path).unwrap();
+
+    let mut cache = LeafCache::new_leafcache128(b"disk", 1000);
+    cache.set_len(100);
+    let mut saved = Vec::new();
+    cache.save(&mut saved, &metadata).unwrap();
+    fs::remove_file(&path).unwrap();
+
+    // magic, version, rate, blocksize, custom, file length, then seconds and nanoseconds
+    let at = 16 + 1 + 2 + 8 + 8 + 4 + 8;
+    for &(secs, nanos) in &[(0, 1_000_000_000), (u64::MAX, 999_999_999), (u64::MAX, u32::MAX)] {
+        let mut bytes = saved.clone();
+        bytes[at..at + 8].copy_from_slice(&secs.to_be_bytes());
+        bytes[at + 8..at + 12].copy_from_slice(&nanos.to_be_bytes());
+
+        // a crafted cache, with a valid checksum
+        let body = bytes.len() - 32;
+        let mut checksum = [0; 32];
+        hash_id(b"sp800-185 leaves", &bytes[..body], &mut checksum);
+        bytes[body..].copy_from_slice(&checksum);
+
+        let err = LeafCache::load(&bytes[..], &metadata).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    }
+}
+
+#[test]
+fn test_leafcache_large_leaf() {
+    // leaves longer than what digest reads at once
+    let blocksize = (1 << 24) + 1;
+    let data = (0..blocksize + 100).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut expected = [0; 32];
+    let mut hasher = ParallelHash::new_parallelhash128(b"", blocksize);
+    hasher.update(&data);
+    hasher.finalize(&mut expected);
+
+    let mut buf = [0; 32];
+    let mut cache = LeafCache::new_leafcache128(b"", blocksize);
+    cache.set_len(data.len() as u64);
+    cache.digest(Cursor::new(&data), &mut buf).unwrap();
+    assert_eq!(buf, expected);
+}
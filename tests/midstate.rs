@@ -0,0 +1,169 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, KMac, TupleHash };
+use sp800_185::midstate::{ MidstateError, MIDSTATE_LEN, SEALED_LEN };
+
+
+#[test]
+fn test_midstate_cshake() {
+    let data = [0x5a; 1000];
+    let mut expected = [0; 64];
+    let mut buf = [0; 64];
+
+    let mut cshake = CShake::new_cshake128(b"", b"Email Signature");
+    cshake.update(&data);
+    cshake.finalize(&mut expected);
+
+    for &split in &[0, 1, 167, 168, 500] {
+        let mut cshake = CShake::new_cshake128(b"", b"Email Signature");
+        cshake.update(&data[..split]);
+        let midstate = cshake.export_midstate();
+        assert_eq!(midstate.len(), MIDSTATE_LEN);
+
+        let mut cshake = CShake::import_midstate(&midstate).unwrap();
+        cshake.update(&data[split..]);
+        cshake.finalize(&mut buf);
+        assert_eq!(buf, expected);
+    }
+}
+
+#[test]
+fn test_midstate_tuplehash() {
+    let mut expected = [0; 32];
+    let mut buf = [0; 32];
+
+    let mut tuplehash = TupleHash::new_tuplehash256(b"My Tuple App");
+    tuplehash.update(&[&b"abc"[..], b"d"]);
+    tuplehash.finalize(&mut expected);
+
+    let mut tuplehash = TupleHash::new_tuplehash256(b"My Tuple App");
+    tuplehash.update(&[b"abc"]);
+    let midstate = tuplehash.export_midstate();
+    assert_eq!(CShake::import_midstate(&midstate).err(), Some(MidstateError::Kind));
+
+    let mut tuplehash = TupleHash::import_midstate(&midstate).unwrap();
+    tuplehash.update(&[b"d"]);
+    tuplehash.finalize(&mut buf);
+    assert_eq!(buf, expected);
+}
+
+#[test]
+fn test_midstate_kmac() {
+    let mut expected = [0; 32];
+    let mut buf = [0; 32];
+
+    let mut kmac = KMac::new_kmac128(b"kmac key", b"");
+    kmac.update(b"first request, second request");
+    kmac.finalize(&mut expected);
+
+    let mut kmac = KMac::new_kmac128(b"kmac key", b"");
+    kmac.update(b"first request, ");
+    let sealed = kmac.export_sealed(b"sealing key");
+    assert_eq!(sealed.len(), SEALED_LEN);
+    assert_eq!(&sealed[..], &kmac.export_sealed(b"sealing key")[..]);
+
+    // the state is encrypted
+    let clear = kmac.dangerous_export_midstate();
+    assert!(!sealed.windows(64).any(|w| clear[2..].windows(64).any(|c| c == w)));
+
+    assert_eq!(KMac::import_sealed(b"other key", &sealed).err(), Some(MidstateError::Tag));
+    for i in 2..SEALED_LEN {
+        let mut tampered = sealed;
+        tampered[i] ^= 1;
+        assert_eq!(KMac::import_sealed(b"sealing key", &tampered).err(), Some(MidstateError::Tag));
+    }
+
+    let mut kmac = KMac::import_sealed(b"sealing key", &sealed).unwrap();
+    kmac.update(b"second request");
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, expected);
+
+    let mut kmac = KMac::dangerous_import_midstate(&clear).unwrap();
+    kmac.update(b"second request");
+    assert!(kmac.verify(&expected));
+}
+
+#[test]
+fn test_midstate_invalid() {
+    let midstate = CShake::new_cshake256(b"", b"").export_midstate();
+    assert!(CShake::import_midstate(&midstate).is_ok());
+
+    assert_eq!(CShake::import_midstate(&midstate[1..]).err(), Some(MidstateError::Length));
+
+    let mut bad = midstate;
+    bad[0] = 0;
+    assert_eq!(CShake::import_midstate(&bad).err(), Some(MidstateError::Version));
+
+    // version 1 had another layout
+    assert_eq!(midstate[0], 2);
+    let mut bad = midstate;
+    bad[0] = 1;
+    assert_eq!(CShake::import_midstate(&bad).err(), Some(MidstateError::Version));
+
+    // rate / 8, offset, delimiter, rounds
+    for &(i, value) in &[(202, 0), (202, 22), (203, 136), (204, 0x1f), (205, 12)] {
+        let mut bad = midstate;
+        bad[i] = value;
+        assert_eq!(CShake::import_midstate(&bad).err(), Some(MidstateError::Invalid));
+    }
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_midstate_parallelhash() {
+    use sp800_185::ParallelHash;
+    use sp800_185::midstate::PARALLELHASH_LEN;
+
+    let data = (0..10_000u32).map(|i| i as u8).collect::<Vec<u8>>();
+    let mut expected = [0; 64];
+    let mut buf = [0; 64];
+
+    let mut hasher = ParallelHash::new_parallelhash256(b"upload", 1000);
+    hasher.update(&data);
+    hasher.finalize(&mut expected);
+
+    for &split in &[0, 999, 1000, 4321, 10_000] {
+        let mut hasher = ParallelHash::new_parallelhash256(b"upload", 1000);
+        hasher.update(&data[..split]);
+        let midstate = hasher.export_midstate();
+        assert_eq!(midstate.len(), PARALLELHASH_LEN);
+
+        let mut hasher = ParallelHash::import_midstate(&midstate).unwrap();
+        assert_eq!(hasher.progress(), ParallelHash::import_midstate(&midstate).unwrap().progress());
+        assert_eq!(hasher.progress().bytes, split as u64);
+        hasher.update(&data[split..]);
+        hasher.finalize(&mut buf);
+        assert_eq!(&buf[..], &expected[..]);
+    }
+
+    // partial leaf as long as the block
+    let mut midstate = ParallelHash::new_parallelhash256(b"upload", 1000).export_midstate();
+    midstate[2 + 24..2 + 32].copy_from_slice(&1000u64.to_le_bytes());
+    assert_eq!(ParallelHash::import_midstate(&midstate).err(), Some(MidstateError::Invalid));
+
+    // bytes absorbed not n * blocksize + partial_len
+    let mut hasher = ParallelHash::new_parallelhash256(b"upload", 1000);
+    hasher.update(&data[..4321]);
+    let midstate = hasher.export_midstate();
+    for &bytes in &[0, 4320, 4322, 5321, u64::MAX] {
+        let mut bad = midstate;
+        bad[2 + 16..2 + 24].copy_from_slice(&bytes.to_le_bytes());
+        assert_eq!(ParallelHash::import_midstate(&bad).err(), Some(MidstateError::Invalid));
+    }
+
+    // n * blocksize overflows
+    let mut bad = midstate;
+    bad[2 + 8..2 + 16].copy_from_slice(&u64::MAX.to_le_bytes());
+    assert_eq!(ParallelHash::import_midstate(&bad).err(), Some(MidstateError::Invalid));
+
+    // leaf digests count as whole leaves
+    let digests = data[..4000].chunks(1000)
+        .map(ParallelHash::leaf_digest256)
+        .collect::<Vec<_>>();
+    let hasher = ParallelHash::from_leaf_digests256(b"upload", 1000, digests.iter().map(|z| &z[..]), 4);
+    assert_eq!(hasher.progress().bytes, 4000);
+    let mut hasher = ParallelHash::import_midstate(&hasher.export_midstate()).unwrap();
+    hasher.update(&data[4000..]);
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &expected[..]);
+}
@@ -0,0 +1,93 @@
+#![cfg(feature = "std")]
+
+extern crate sp800_185;
+
+use std::io::{ self, Write };
+use sp800_185::ParallelHash;
+use sp800_185::outboard::{ Outboard, VerifyError };
+
+
+#[test]
+fn test_outboard() {
+    let data = (0..10500u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut root = [0; 32];
+    let mut hasher = ParallelHash::new_parallelhash128(b"blob", 1000);
+    hasher.update(&data);
+    hasher.finalize(&mut root);
+
+    let outboard = Outboard::new_outboard128(b"blob", 1000, &data);
+    let mut buf = [0; 32];
+    outboard.root(&mut buf);
+    assert_eq!(buf, root);
+
+    let bytes = outboard.to_bytes();
+    let outboard = Outboard::from_bytes(&bytes, &root).unwrap();
+    assert_eq!(outboard.len(), 10500);
+    assert_eq!(outboard.blocks(), 11);
+
+    // random access
+    outboard.verify_block(10, &data[10000..]).unwrap();
+    outboard.verify_range(3000, &data[3000..5000]).unwrap();
+    outboard.verify_range(9000, &data[9000..]).unwrap();
+    assert_eq!(outboard.verify_range(3000, &data[3000..4500]), Err(VerifyError::InvalidRange));
+    assert_eq!(outboard.verify_range(3500, &data[3500..5000]), Err(VerifyError::InvalidRange));
+    assert_eq!(outboard.verify_block(11, &[]), Err(VerifyError::InvalidRange));
+
+    let mut bad = data.clone();
+    bad[4321] ^= 1;
+    assert_eq!(outboard.verify_range(3000, &bad[3000..6000]), Err(VerifyError::BlockMismatch(4)));
+
+    // tampered leaf list
+    let mut tampered = bytes.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert_eq!(Outboard::from_bytes(&tampered, &root), Err(VerifyError::RootMismatch));
+    assert_eq!(Outboard::from_bytes(&bytes[..bytes.len() - 1], &root), Err(VerifyError::InvalidOutboard));
+
+    // short roots are rejected, even the right ones
+    assert_eq!(Outboard::from_bytes(&bytes, &[]), Err(VerifyError::ShortRoot));
+    assert_eq!(Outboard::from_bytes(&tampered, &[]), Err(VerifyError::ShortRoot));
+    assert_eq!(Outboard::from_bytes(&bytes, &root[..16]), Err(VerifyError::ShortRoot));
+    let mut short = [0; 16];
+    outboard.root(&mut short);
+    assert_eq!(Outboard::from_bytes(&bytes, &short), Err(VerifyError::ShortRoot));
+
+    let mut long = [0; 64];
+    outboard.root(&mut long);
+    assert!(Outboard::from_bytes(&bytes, &long).is_ok());
+    let outboard = Outboard::new_outboard256(b"blob", 1000, &data);
+    assert_eq!(Outboard::from_bytes(&outboard.to_bytes(), &long[..32]), Err(VerifyError::ShortRoot));
+}
+
+#[test]
+fn test_outboard_decoder() {
+    let data = (0..10500u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+    let outboard = Outboard::new_outboard256(b"", 1000, &data);
+
+    let mut decoder = outboard.decoder(Vec::new());
+    for chunk in data.chunks(333) {
+        decoder.write_all(chunk).unwrap();
+    }
+    assert_eq!(decoder.finish().unwrap(), data);
+
+    // a bad block is rejected before any of it is written
+    let mut bad = data.clone();
+    bad[2500] ^= 1;
+    let mut output = Vec::new();
+    {
+        let mut decoder = outboard.decoder(&mut output);
+        let err = decoder.write_all(&bad).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert!(decoder.write_all(&bad[3000..]).is_err());
+        assert!(decoder.finish().is_err());
+    }
+    assert_eq!(output, &data[..2000]);
+
+    let mut decoder = outboard.decoder(Vec::new());
+    decoder.write_all(&data[..10000]).unwrap();
+    assert_eq!(decoder.finish().unwrap_err().kind(), io::ErrorKind::UnexpectedEof);
+
+    let mut decoder = outboard.decoder(Vec::new());
+    decoder.write_all(&data).unwrap();
+    assert!(decoder.write_all(b"trailing").is_err());
+}
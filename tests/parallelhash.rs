@@ -1,8 +1,11 @@
-#![cfg(feature = "parallelhash")]
+#![cfg(feature = "alloc")]
 
 extern crate sp800_185;
+#[cfg(feature = "rayon")] extern crate rayon;
 
-use sp800_185::ParallelHash;
+use std::sync::Arc;
+use std::sync::atomic::{ AtomicUsize, Ordering };
+use sp800_185::{ ParallelHash, Backend, Executor, Leaves, Progress, CancelToken, Cancelled };
 
 
 #[test]
@@ -124,3 +127,299 @@ fn test_parallelhash256_xof() {
     xof.squeeze(&mut buf);
     assert_eq!(buf, &output[..]);
 }
+
+#[test]
+fn test_parallelhash_backends() {
+    let input = (0..10007u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    #[allow(unused_mut)]
+    let mut backends = vec![Backend::Sequential];
+    #[cfg(feature = "std")] backends.push(Backend::Threads);
+    #[cfg(feature = "rayon")] backends.push(Backend::Rayon);
+
+    for &blocksize in &[1, 8, 100, 1024] {
+        let mut output = [0; 64];
+        let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", blocksize)
+            .with_backend(Backend::Sequential);
+        hasher.update(&input);
+        hasher.finalize(&mut output);
+
+        for &backend in &backends {
+            // uneven updates cross leaf boundaries
+            let mut buf = [0; 64];
+            let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", blocksize)
+                .with_backend(backend);
+            for chunk in input.chunks(333) {
+                hasher.update(chunk);
+            }
+            hasher.finalize(&mut buf);
+            assert_eq!(&buf[..], &output[..], "{:?} {}", backend, blocksize);
+        }
+    }
+}
+
+#[test]
+#[should_panic(expected = "leaves not hashed")]
+fn test_parallelhash_executor_unhashed() {
+    /// Hashes the first half of every batch, drops the rest.
+    struct FirstHalf;
+
+    impl Executor for FirstHalf {
+        fn execute(&self, leaves: Leaves) {
+            let mid = leaves.len() / 2;
+            leaves.split_at(mid).0.hash();
+        }
+    }
+
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 8)
+        .with_executor(FirstHalf)
+        .with_min_batch(0);
+    hasher.update(&[0; 800]);
+}
+
+#[test]
+fn test_parallelhash_executor() {
+    /// Splits every batch in two and counts the batches.
+    struct Halves(AtomicUsize);
+
+    impl Executor for Halves {
+        fn execute(&self, leaves: Leaves) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+            let mid = leaves.len() / 2 / Leaves::LANES * Leaves::LANES;
+            let (a, b) = leaves.split_at(mid);
+            a.hash();
+            for leaves in b.chunks(Leaves::LANES) {
+                leaves.hash();
+            }
+        }
+    }
+
+    let input = (0..10007u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut output = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", 8)
+        .with_backend(Backend::Sequential);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    // 1250 leaves, in batches of at most 100, the last 50 under the threshold
+    let executor = Arc::new(Halves(AtomicUsize::new(0)));
+    let mut buf = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", 8)
+        .with_executor(executor.clone())
+        .with_min_batch(60)
+        .with_max_in_flight(100);
+    hasher.update(&input);
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+    assert_eq!(executor.0.load(Ordering::Relaxed), 12);
+
+    // small updates stay on the calling thread
+    executor.0.store(0, Ordering::Relaxed);
+    let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", 8)
+        .with_executor(executor.clone());
+    for chunk in input.chunks(100) {
+        hasher.update(chunk);
+    }
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+    assert_eq!(executor.0.load(Ordering::Relaxed), 0);
+
+    #[cfg(feature = "rayon")] {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let mut hasher = ParallelHash::new_parallelhash256(b"Parallel Data", 8)
+            .with_executor(pool)
+            .with_min_batch(0);
+        hasher.update(&input);
+        hasher.finalize(&mut buf);
+        assert_eq!(&buf[..], &output[..]);
+    }
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_parallelhash_reader() {
+    use std::{ fs, io };
+
+    /// Short reads of varying length, then an optional error.
+    struct Choppy<'a>(&'a [u8], usize, bool);
+
+    impl<'a> io::Read for Choppy<'a> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            if self.0.is_empty() && self.2 {
+                return Err(io::Error::other("choppy"));
+            }
+            self.1 = self.1 % 997 + 13;
+            let len = self.1.min(buf.len()).min(self.0.len());
+            buf[..len].copy_from_slice(&self.0[..len]);
+            self.0 = &self.0[len..];
+            Ok(len)
+        }
+    }
+
+    let input = (0..(2 << 20) + 12345u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    for &blocksize in &[1000, 8192] {
+        let mut output = [0; 64];
+        let mut hasher = ParallelHash::new_parallelhash256(b"", blocksize);
+        hasher.update(&input);
+        hasher.finalize(&mut output);
+
+        let mut buf = [0; 64];
+        let mut hasher = ParallelHash::new_parallelhash256(b"", blocksize);
+        hasher.update(&input[..5]);
+        let n = hasher.update_reader(Choppy(&input[5..], 0, false)).unwrap();
+        assert_eq!(n, input.len() as u64 - 5);
+        hasher.finalize(&mut buf);
+        assert_eq!(&buf[..], &output[..]);
+
+        // everything read before the error is absorbed, the partial buffer included
+        let mut hasher = ParallelHash::new_parallelhash256(b"", blocksize);
+        assert!(hasher.update_reader(Choppy(&input, 0, true)).is_err());
+        assert_eq!(hasher.progress().bytes, input.len() as u64);
+        hasher.finalize(&mut buf);
+        assert_eq!(&buf[..], &output[..]);
+    }
+
+    let path = std::env::temp_dir().join(format!("sp800-185-{}", std::process::id()));
+    fs::write(&path, &input).unwrap();
+
+    let mut output = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 8192);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    let mut buf = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 8192);
+    let n = hasher.hash_file(&path);
+    fs::remove_file(&path).unwrap();
+    assert_eq!(n.unwrap(), input.len() as u64);
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+}
+
+#[test]
+fn test_parallelhash_leaf_digests() {
+    let input = (0..10007u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut output = [0; 32];
+    let mut hasher = ParallelHash::new_parallelhash128(b"Parallel Data", 1000);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    let digests = input.chunks(1000)
+        .map(ParallelHash::leaf_digest128)
+        .collect::<Vec<_>>();
+    let mut buf = [0; 32];
+    let hasher = ParallelHash::from_leaf_digests128(b"Parallel Data", 1000, &digests, 11);
+    hasher.finalize(&mut buf);
+    assert_eq!(buf, output);
+
+    // digests of a prefix, then the rest of the input
+    let mut output = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"", 8);
+    hasher.update(&input);
+    let mut xof = hasher.xof();
+    xof.squeeze(&mut output);
+
+    let digests = input[..800].chunks(8)
+        .map(ParallelHash::leaf_digest256)
+        .collect::<Vec<_>>();
+    let mut buf = [0; 64];
+    let mut hasher = ParallelHash::from_leaf_digests256(b"", 8, digests.iter().map(|z| &z[..]), 100);
+    hasher.update(&input[800..]);
+    let mut xof = hasher.xof();
+    xof.squeeze(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+}
+
+#[test]
+#[should_panic(expected = "missing or extra leaf digests")]
+fn test_parallelhash_leaf_digests_missing() {
+    let digests = vec![[0; 32]; 10];
+    ParallelHash::from_leaf_digests128(b"", 8, &digests, 11);
+}
+
+#[test]
+#[should_panic(expected = "blocksize must be positive")]
+fn test_parallelhash_zero_blocksize() {
+    ParallelHash::from_leaf_digests256(b"", 0, [[0; 64]], 1);
+}
+
+#[test]
+fn test_parallelhash_progress_cancel() {
+    let input = (0..100_000u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut output = [0; 64];
+    let mut hasher = ParallelHash::new_parallelhash256(b"", 100);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    // cancelled from the progress callback once 300 leaves are done
+    let token = CancelToken::new();
+    let reports = Arc::new(AtomicUsize::new(0));
+    let mut hasher = ParallelHash::new_parallelhash256(b"", 100)
+        .with_max_in_flight(100)
+        .with_cancel(token.clone())
+        .with_progress({
+            let token = token.clone();
+            let reports = reports.clone();
+            move |progress: Progress| {
+                reports.fetch_add(1, Ordering::Relaxed);
+                assert_eq!(progress.bytes, progress.leaves * 100);
+                if progress.leaves >= 300 {
+                    token.cancel();
+                }
+            }
+        });
+
+    hasher.update(&input[..50]);
+    assert_eq!(hasher.try_update(&input[50..]), Err(Cancelled { consumed: 30050 }));
+    assert_eq!(hasher.progress(), Progress { bytes: 30100, leaves: 301 });
+    assert_eq!(reports.load(Ordering::Relaxed), 3);
+
+    // update ignores the token
+    hasher.update(&input[30100..40120]);
+    assert_eq!(hasher.progress(), Progress { bytes: 40120, leaves: 401 });
+
+    let mut hasher = hasher.with_cancel(CancelToken::new());
+    hasher.try_update(&input[40120..]).unwrap();
+    let mut buf = [0; 64];
+    hasher.finalize(&mut buf);
+    assert_eq!(&buf[..], &output[..]);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_parallelhash_reader_cancel() {
+    use std::io::{ self, Cursor, Seek, SeekFrom };
+
+    let input = (0..(3 << 20) + 12345u32).map(|i| (i * 31 + 7) as u8).collect::<Vec<u8>>();
+
+    let mut output = [0; 32];
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 8192);
+    hasher.update(&input);
+    hasher.finalize(&mut output);
+
+    let token = CancelToken::new();
+    let mut hasher = ParallelHash::new_parallelhash128(b"", 8192)
+        .with_cancel(token.clone())
+        .with_progress(move |progress| if progress.bytes >= 1 << 20 {
+            token.cancel();
+        });
+
+    let mut reader = Cursor::new(&input);
+    let err = hasher.update_reader(&mut reader).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::Other);
+    assert!(err.get_ref().unwrap().is::<Cancelled>());
+
+    let absorbed = hasher.progress().bytes;
+    assert!(absorbed >= 1 << 20 && absorbed < input.len() as u64);
+    reader.seek(SeekFrom::Start(absorbed)).unwrap();
+    let mut hasher = hasher.with_cancel(CancelToken::new());
+    hasher.update_reader(&mut reader).unwrap();
+
+    let mut buf = [0; 32];
+    hasher.finalize(&mut buf);
+    assert_eq!(buf, output);
+}
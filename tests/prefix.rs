@@ -0,0 +1,54 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, KMac, TupleHash, Prefix };
+
+
+static CSHAKE128: Prefix = Prefix::new_cshake128(b"name", b"a customization string longer than one block of cSHAKE256, \
+    so that the prefix is more than one rate of input and permuted along the way........");
+static CSHAKE256: Prefix = Prefix::new_cshake256(b"", b"Email Signature");
+static KMAC256: Prefix = Prefix::new_kmac256(b"My Tagged Application");
+static TUPLEHASH128: Prefix = Prefix::new_tuplehash128(b"My Tuple App");
+
+#[test]
+fn test_prefix() {
+    let mut expected = [0; 64];
+    let mut buf = [0; 64];
+
+    let mut cshake = CShake::new_cshake128(CSHAKE128.name(), CSHAKE128.custom());
+    cshake.update(b"input");
+    cshake.finalize(&mut expected);
+    let mut cshake = CShake::from_prefix(&CSHAKE128);
+    cshake.update(b"input");
+    cshake.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+
+    let mut cshake = CShake::new_cshake256(b"", b"Email Signature");
+    cshake.update(b"input");
+    cshake.finalize(&mut expected);
+    let mut cshake = CShake::from_prefix(&CSHAKE256);
+    cshake.update(b"input");
+    cshake.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+
+    let mut kmac = KMac::new_kmac256(b"key", b"My Tagged Application");
+    kmac.update(b"input");
+    kmac.finalize(&mut expected);
+    let mut kmac = KMac::from_prefix(&KMAC256, b"key");
+    kmac.update(b"input");
+    kmac.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+
+    let mut tuplehash = TupleHash::new_tuplehash128(b"My Tuple App");
+    tuplehash.update(&[&b"a"[..], b"bc"]);
+    tuplehash.finalize(&mut expected);
+    let mut tuplehash = TupleHash::from_prefix(&TUPLEHASH128);
+    tuplehash.update(&[&b"a"[..], b"bc"]);
+    tuplehash.finalize(&mut buf);
+    assert_eq!(buf[..], expected[..]);
+}
+
+#[test]
+#[should_panic(expected = "not a KMAC prefix")]
+fn test_prefix_mismatch() {
+    KMac::from_prefix(&TUPLEHASH128, b"key");
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "recorder")]
+
+extern crate sp800_185;
+
+use sp800_185::{ KMac, TupleHash };
+use sp800_185::recorder::record;
+
+
+#[test]
+fn test_record_kmac() {
+    let key = b"\x40\x41\x42\x43\x44\x45\x46\x47\x48\x49\x4A\x4B\x4C\x4D\x4E\x4F\x50\x51\x52\x53\x54\x55\x56\x57\x58\x59\x5A\x5B\x5C\x5D\x5E\x5F";
+    let data = b"\x00\x01\x02\x03";
+
+    let mut buf = [0; 32];
+    let ((), recording) = record(|| {
+        let mut kmac = KMac::new_kmac128(key, b"");
+        kmac.update(data);
+        kmac.finalize(&mut buf);
+    });
+    assert_eq!(&buf[..4], b"\xE5\x78\x0B\x0D");
+
+    let labels = recording.segments().iter()
+        .map(|seg| seg.label)
+        .collect::<Vec<_>>();
+    assert_eq!(labels, [
+        "left_encode(rate)", "encode_string(N)", "encode_string(S)", "bytepad zero fill",
+        "left_encode(rate)", "encode_string(K)", "bytepad zero fill",
+        "X", "right_encode(L)", "pad"
+    ]);
+
+    let segments = recording.segments();
+    assert_eq!(segments[0].bytes, b"\x01\xa8");
+    assert_eq!(segments[1].bytes, b"\x01\x20KMAC");
+    assert_eq!(segments[2].bytes, b"\x01\x00");
+    assert_eq!(segments[3].bytes, vec![0; 168 - 10]);
+    assert_eq!(segments[5].bytes[..3], b"\x02\x01\x00"[..]);
+    assert_eq!(segments[6].bytes.len(), 168 - 2 - 35);
+    assert_eq!(segments[8].bytes, b"\x01\x00\x02");
+    assert_eq!(segments[9].bytes.len(), 168 - 7);
+    assert_eq!(segments[9].bytes[0], 0x04);
+    assert_eq!(segments[9].bytes[168 - 8], 0x80);
+
+    // nothing is recorded outside `record`
+    let mut kmac = KMac::new_kmac128(key, b"");
+    kmac.update(data);
+    let ((), recording) = record(|| kmac.finalize(&mut buf));
+    assert_eq!(recording.segments().len(), 2);
+}
+
+#[test]
+fn test_record_display() {
+    let ((), recording) = record(|| {
+        let mut hasher = TupleHash::new_tuplehash128(b"");
+        hasher.update(&[b"\x00\x01\x02"]);
+        hasher.update_value(&1u8);
+    });
+
+    let text = recording.to_string();
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "left_encode(rate)   01 a8");
+    assert_eq!(lines[1], "encode_string(N)    01 48 54 75 70 6c 65 48 61 73 68");
+    assert_eq!(lines[2], "encode_string(S)    01 00");
+    assert_eq!(lines[3], "bytepad zero fill   00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+    assert_eq!(lines[4], "                    00 00 00 00 00 00 00 00 00 00 00 00 00 00 00 00");
+    assert_eq!(lines[13], "encode_string(X[i]) 01 18 00 01 02");
+    assert_eq!(lines[14], "encode_string(X[i]) 01 10 02 01");
+}
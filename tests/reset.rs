@@ -0,0 +1,107 @@
+extern crate sp800_185;
+
+use sp800_185::{
+    Shake, CShake, KMac, TupleHash, TurboShake, KangarooTwelve,
+    FixedParallelHash, Duplex, Transcript
+};
+use sp800_185::function::{ FunctionBuilder, Output };
+
+
+macro_rules! check_reset {
+    ( $new:expr, $hasher:ident => $update:expr ) => {{
+        let mut expected = [0; 32];
+        let mut buf = [0; 32];
+
+        {
+            let mut $hasher = $new;
+            $update;
+            $hasher.finalize(&mut expected);
+        }
+
+        let mut $hasher = $new;
+        for _ in 0..3 {
+            $update;
+            $hasher.finalize_reset(&mut buf);
+            assert_eq!(buf, expected);
+        }
+
+        $update;
+        $update;
+        $hasher.reset();
+        $update;
+        $hasher.finalize(&mut buf);
+        assert_eq!(buf, expected);
+    }}
+}
+
+#[test]
+fn test_reset() {
+    let data = [0xa5; 300];
+
+    check_reset!(Shake::new_shake128(), h => h.update(&data));
+    check_reset!(TurboShake::new_turboshake256(0x1f), h => h.update(&data));
+    check_reset!(CShake::new_cshake128(b"", b"Email Signature"), h => h.update(&data));
+    check_reset!(KMac::new_kmac256(b"key", b"custom"), h => h.update(&data));
+    check_reset!(TupleHash::new_tuplehash128(b"custom"), h => h.update(&[&data[..], b"x"]));
+    check_reset!(KangarooTwelve::new_kt128(b"custom"), h => h.update(&[0x42; 10_000]));
+    check_reset!(FixedParallelHash::<64>::new_parallelhash128(b"custom"), h => h.update(&data));
+
+    let builder = FunctionBuilder::new_function256(b"MyFunction").with_output(Output::BoundLength);
+    check_reset!(builder.build().unwrap(), h => h.update(&data));
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_reset_parallelhash() {
+    use sp800_185::ParallelHash;
+
+    let data = [0xa5; 300];
+    check_reset!(ParallelHash::new_parallelhash256(b"custom", 64), h => h.update(&data));
+
+    let mut hasher = ParallelHash::new_parallelhash256(b"custom", 64);
+    hasher.update(&data);
+    hasher.reset();
+    assert_eq!(hasher.progress().bytes, 0);
+    assert_eq!(hasher.progress().leaves, 0);
+}
+
+#[test]
+fn test_reset_kmac_midstate() {
+    let mut expected = [0; 32];
+    let mut buf = [0; 32];
+
+    let mut kmac = KMac::new_kmac128(b"key", b"");
+    kmac.update(b"data");
+    kmac.finalize_reset(&mut expected);
+
+    // the imported hasher resets to the state after the key, not to the import
+    kmac.update(b"other");
+    let mut kmac = KMac::import_sealed(b"sealing key", &kmac.export_sealed(b"sealing key")).unwrap();
+    kmac.reset();
+    kmac.update(b"data");
+    kmac.finalize(&mut buf);
+    assert_eq!(buf, expected);
+}
+
+#[test]
+fn test_reset_duplex_transcript() {
+    let mut expected = [0; 32];
+    let mut buf = [0; 32];
+
+    let mut duplex = Duplex::new_duplex128(b"", b"protocol");
+    duplex.absorb(b"key");
+    duplex.squeeze(&mut expected);
+    duplex.absorb(b"more");
+    duplex.reset();
+    duplex.absorb(b"key");
+    duplex.squeeze(&mut buf);
+    assert_eq!(buf, expected);
+
+    let mut transcript = Transcript::new_transcript256(b"protocol");
+    transcript.append_message(b"label", b"message");
+    transcript.challenge_bytes(b"challenge", &mut expected);
+    transcript.reset();
+    transcript.append_message(b"label", b"message");
+    transcript.challenge_bytes(b"challenge", &mut buf);
+    assert_eq!(buf, expected);
+}
@@ -0,0 +1,124 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, Shake, XofStream };
+
+
+fn stream() -> XofStream {
+    let mut cshake = CShake::new_cshake128(b"", b"sample");
+    cshake.update(b"seed");
+    cshake.xof()
+}
+
+#[test]
+fn test_uniform() {
+    let mut xof = stream();
+    let values = [1, 2, 3, 10, 1000, 3329, 1 << 40, u64::MAX, 12345678901]
+        .iter()
+        .map(|&bound| xof.uniform(bound))
+        .collect::<Vec<u64>>();
+    assert_eq!(values, [0, 1, 2, 9, 705, 698, 130693729858, 6080253688171459495, 3122020240]);
+}
+
+// The expected values below follow the algorithms of FIPS 203 and FIPS 204 as written, run on
+// the SHAKE of Python's hashlib, with the seed 00 01 .. 1f.
+
+fn seed() -> Vec<u8> {
+    (0..32).collect()
+}
+
+fn shake128(input: &[u8]) -> XofStream {
+    let mut shake = Shake::new_shake128();
+    shake.update(input);
+    shake.xof()
+}
+
+fn shake256(input: &[u8]) -> XofStream {
+    let mut shake = Shake::new_shake256();
+    shake.update(input);
+    shake.xof()
+}
+
+#[test]
+fn test_sample_mod_q() {
+    let mut a = [0; 256];
+
+    // FIPS 203 SampleNTT, SHAKE128(rho || j || i) with j = 1, i = 2
+    let mut xof = shake128(&[&seed()[..], &[1, 2]].concat());
+    xof.sample_mod_q(3329, &mut a);
+    assert_eq!(a[..8], [1642, 1316, 3309, 3204, 1436, 289, 1683, 2323]);
+    assert_eq!(a[252..], [1181, 1973, 2847, 1454]);
+    assert_eq!(a.iter().sum::<u32>(), 426074);
+    assert_eq!(xof.squeezed(), 453);
+
+    // FIPS 204 RejNTTPoly, SHAKE128(rho || s || r) with s = 1, r = 2
+    let mut xof = shake128(&[&seed()[..], &[1, 2]].concat());
+    xof.sample_mod_q(8380417, &mut a);
+    assert_eq!(a[..8], [6729218, 5166372, 5885060, 8110369, 1128083, 792570, 6038026, 3945590]);
+    assert_eq!(a[252..], [3284495, 3395635, 3509950, 4725626]);
+    assert_eq!(a.iter().map(|&x| u64::from(x)).sum::<u64>(), 1059267594);
+    assert_eq!(xof.squeezed(), 768);
+}
+
+#[test]
+fn test_sample_cbd() {
+    fn weighted(s: &[i32]) -> i32 {
+        s.iter().enumerate().map(|(i, &x)| x * (i as i32 + 1)).sum()
+    }
+
+    let mut s = [0; 256];
+
+    // FIPS 203 SamplePolyCBD on PRF_eta(s, N) = SHAKE256(s || N, 64 * eta)
+    let mut xof = shake256(&[&seed()[..], &[0]].concat());
+    xof.sample_cbd(2, &mut s);
+    assert_eq!(s[..16], [-1, 0, 1, 1, -2, 1, 0, 0, -1, 1, -1, -1, -2, 0, 1, -1]);
+    assert_eq!(weighted(&s), 1425);
+    assert_eq!(xof.squeezed(), 128);
+
+    let mut xof = shake256(&[&seed()[..], &[1]].concat());
+    xof.sample_cbd(3, &mut s);
+    assert_eq!(s[..16], [0, -1, -1, -1, -1, 0, 2, -1, -1, -2, 2, -1, 1, 0, -1, 0]);
+    assert_eq!(weighted(&s), 441);
+    assert_eq!(xof.squeezed(), 192);
+}
+
+#[test]
+fn test_sample_in_ball() {
+    fn nonzero(c: &[i8]) -> (Vec<usize>, Vec<i8>) {
+        c.iter().enumerate()
+            .filter(|&(_, &x)| x != 0)
+            .map(|(i, &x)| (i, x))
+            .unzip()
+    }
+
+    let mut c = [0; 256];
+
+    // FIPS 204 SampleInBall on SHAKE256(c~), ML-DSA-44
+    let mut xof = shake256(&seed());
+    xof.sample_in_ball(39, &mut c);
+    assert_eq!(xof.squeezed(), 48);
+    let (positions, signs) = nonzero(&c);
+    assert_eq!(positions, [
+        3, 7, 9, 19, 30, 44, 57, 61, 66, 69, 77, 78, 90, 91, 99, 113, 115, 136, 145, 152,
+        155, 156, 167, 179, 188, 196, 201, 202, 205, 210, 211, 212, 225, 227, 228, 236, 241, 244, 245
+    ]);
+    assert_eq!(signs, [
+        -1, 1, 1, -1, -1, 1, -1, -1, 1, -1, -1, -1, 1, 1, 1, 1, 1, 1, 1, 1,
+        -1, 1, 1, 1, 1, 1, 1, 1, -1, 1, -1, -1, 1, -1, 1, -1, 1, -1, -1
+    ]);
+
+    // ML-DSA-87, with a 64-byte c~
+    let mut xof = shake256(&(0..64).collect::<Vec<u8>>());
+    xof.sample_in_ball(60, &mut c);
+    assert_eq!(xof.squeezed(), 75);
+    let (positions, signs) = nonzero(&c);
+    assert_eq!(positions, [
+        1, 8, 13, 15, 18, 25, 32, 36, 43, 45, 49, 50, 55, 58, 63, 64, 69, 70, 73, 78,
+        81, 88, 99, 106, 110, 112, 113, 117, 118, 122, 123, 124, 125, 127, 132, 136, 138, 151, 153, 156,
+        157, 162, 163, 167, 175, 182, 193, 194, 195, 200, 202, 206, 208, 210, 221, 229, 233, 240, 246, 255
+    ]);
+    assert_eq!(signs, [
+        1, 1, -1, -1, -1, 1, 1, 1, -1, 1, -1, -1, -1, 1, 1, -1, -1, 1, 1, -1,
+        1, -1, 1, -1, 1, -1, -1, 1, 1, -1, -1, -1, -1, -1, 1, 1, 1, -1, 1, -1,
+        1, 1, 1, -1, 1, 1, -1, 1, 1, -1, 1, -1, -1, -1, -1, -1, -1, 1, 1, 1
+    ]);
+}
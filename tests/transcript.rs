@@ -0,0 +1,74 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, Transcript };
+
+
+#[test]
+fn test_transcript_encoding() {
+    let mut buf = [0; 32];
+    let mut transcript = Transcript::new_transcript128(b"My Protocol");
+    transcript.append_message(b"pk", b"\x00\x01\x02");
+    transcript.challenge_bytes(b"c", &mut buf);
+
+    let mut output = [0; 32];
+    let mut cshake = CShake::new_cshake128(b"Transcript", b"My Protocol");
+    cshake.update(b"\x01\x01\x10pk\x01\x18\x00\x01\x02");
+    cshake.update(b"\x02\x01\x08c\x02\x01\x00");
+    cshake.finalize(&mut output);
+    assert_eq!(buf, output);
+}
+
+#[test]
+fn test_transcript_binding() {
+    let mut c1 = [0; 32];
+    let mut c2 = [0; 32];
+
+    let mut t1 = Transcript::new_transcript256(b"My Protocol");
+    t1.append_message(b"a", b"b");
+    t1.challenge_bytes(b"c", &mut c1);
+
+    let mut t2 = Transcript::new_transcript256(b"My Protocol");
+    t2.append_message(b"a", b"b");
+    t2.challenge_bytes(b"d", &mut c2);
+    assert_ne!(c1, c2);
+
+    // later challenges are bound to the earlier ones
+    t1.append_message(b"e", b"f");
+    t2.append_message(b"e", b"f");
+    t1.challenge_bytes(b"g", &mut c1);
+    t2.challenge_bytes(b"g", &mut c2);
+    assert_ne!(c1, c2);
+
+    // a challenge is not a message of the same length
+    let mut t3 = Transcript::new_transcript256(b"My Protocol");
+    t3.append_message(b"a", b"b");
+    t3.append_message(b"c", &[0; 32]);
+    t3.append_message(b"e", b"f");
+    t3.challenge_bytes(b"g", &mut c2);
+    assert_ne!(c1, c2);
+
+    let mut t4 = Transcript::new_transcript256(b"Other Protocol");
+    t4.append_message(b"a", b"b");
+    t4.challenge_bytes(b"c", &mut c2);
+    let mut t5 = Transcript::new_transcript256(b"My Protocol");
+    t5.append_message(b"a", b"b");
+    t5.challenge_bytes(b"c", &mut c1);
+    assert_ne!(c1, c2);
+}
+
+#[test]
+fn test_transcript_rewind() {
+    let mut c1 = [0; 16];
+    let mut c2 = [0; 16];
+
+    let mut transcript = Transcript::new_transcript128(b"My Protocol");
+    transcript.append_message(b"commitment", b"\x00\x01\x02");
+    let snapshot = transcript.clone();
+
+    transcript.challenge_bytes(b"challenge", &mut c1);
+    transcript.append_message(b"response", b"\x03\x04\x05");
+
+    let mut transcript = snapshot;
+    transcript.challenge_bytes(b"challenge", &mut c2);
+    assert_eq!(c1, c2);
+}
@@ -1,6 +1,6 @@
 extern crate sp800_185;
 
-use sp800_185::TupleHash;
+use sp800_185::{ TupleHash, TupleElement };
 
 
 #[test]
@@ -146,3 +146,67 @@ fn test_tuplehash256_xof() {
     xof.squeeze(&mut buf);
     assert_eq!(buf, &output[..]);
 }
+
+
+#[test]
+fn test_tuplehash_update_value() {
+    fn digest<F: FnOnce(&mut TupleHash)>(f: F) -> [u8; 32] {
+        let mut buf = [0; 32];
+        let mut hasher = TupleHash::new_tuplehash128(b"My Tuple App");
+        f(&mut hasher);
+        hasher.finalize(&mut buf);
+        buf
+    }
+
+    let output = digest(|h| h.update(&[
+        &b"\x04\x00\x00\x00\x2a"[..],
+        &b"\x01\x01"[..],
+        &b"\x20abc"[..],
+        &b"\x30\x01\x15\xff\xff\xff\xff\xff\xff\xff\xfe"[..],
+        &b"\x30\x00"[..]
+    ]));
+    assert_eq!(output, digest(|h| {
+        h.update_value(&42u32);
+        h.update_value(&true);
+        h.update_value("abc");
+        h.update_value(&Some(-2i64));
+        h.update_value(&None::<u8>);
+    }));
+
+    // different types never share an encoding
+    assert_ne!(digest(|h| h.update_value(&1u8)), digest(|h| h.update_value(&1u16)));
+    assert_ne!(digest(|h| h.update_value(&1u8)), digest(|h| h.update_value(&1i8)));
+    assert_ne!(digest(|h| h.update_value("a")), digest(|h| h.update_value(&b"a"[..])));
+    assert_ne!(digest(|h| h.update_value(&Some(1u8))), digest(|h| h.update_value(&1u8)));
+    assert_eq!(digest(|h| h.update_value(&7usize)), digest(|h| h.update_value(&7u64)));
+    assert_eq!(digest(|h| h.update_value(&-7isize)), digest(|h| h.update_value(&-7i64)));
+    assert_eq!(digest(|h| h.update_value(&&&"abc")), digest(|h| h.update_value("abc")));
+
+    assert_eq!(true.encoded_len(), 2);
+    assert_eq!(0u128.encoded_len(), 17);
+    assert_eq!(Some("abc").encoded_len(), 6);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_tuplehash_update_systemtime() {
+    use std::time::{ Duration, UNIX_EPOCH };
+
+    let mut buf = [0; 13];
+    let mut pos = 0;
+    let time = UNIX_EPOCH + Duration::new(1_500_000_000, 5);
+    time.encode(&mut |b: &[u8]| {
+        buf[pos..][..b.len()].copy_from_slice(b);
+        pos += b.len();
+    });
+    assert_eq!(pos, time.encoded_len());
+    assert_eq!(buf, *b"\x40\x00\x00\x00\x00\x59\x68\x2f\x00\x00\x00\x00\x05");
+
+    let mut pos = 0;
+    let time = UNIX_EPOCH - Duration::new(1, 250_000_000);
+    time.encode(&mut |b: &[u8]| {
+        buf[pos..][..b.len()].copy_from_slice(b);
+        pos += b.len();
+    });
+    assert_eq!(buf, *b"\x40\xff\xff\xff\xff\xff\xff\xff\xfe\x2c\xb4\x17\x80");
+}
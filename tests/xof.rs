@@ -0,0 +1,74 @@
+extern crate sp800_185;
+
+use sp800_185::{ CShake, XofStream };
+
+
+fn stream() -> XofStream {
+    let mut cshake = CShake::new_cshake128(b"", b"xof");
+    cshake.update(b"input");
+    cshake.xof()
+}
+
+#[test]
+fn test_xof_stream() {
+    let mut expected = vec![0; 1000];
+    stream().fill(&mut expected);
+
+    for &skip in &[0, 1, 100, 167, 168, 169, 336, 337, 500] {
+        let mut xof = stream();
+        xof.skip(skip);
+        let mut buf = vec![0; 1000 - skip as usize];
+        xof.fill(&mut buf);
+        assert_eq!(buf, &expected[skip as usize..]);
+        assert_eq!(xof.squeezed(), 1000);
+    }
+
+    // skip from the middle of a block
+    let mut xof = stream();
+    xof.skip(10);
+    xof.skip(400);
+    let mut buf = [0; 20];
+    xof.fill(&mut buf);
+    assert_eq!(&buf[..], &expected[410..430]);
+
+    let mut xof = stream();
+    assert_eq!(xof.read_u32_le(), u32::from_le_bytes([expected[0], expected[1], expected[2], expected[3]]));
+    let mut word = [0; 8];
+    word.copy_from_slice(&expected[4..12]);
+    assert_eq!(xof.read_u64_le(), u64::from_le_bytes(word));
+    assert_eq!(xof.squeezed(), 12);
+
+    let bytes = xof.bytes().take(100).collect::<Vec<u8>>();
+    assert_eq!(bytes, &expected[12..112]);
+
+    let blocks = xof.blocks().take(3).collect::<Vec<_>>();
+    assert_eq!(blocks[0].len(), 168 - 112);
+    assert_eq!(&blocks[0][..], &expected[112..168]);
+    assert_eq!(&blocks[1][..], &expected[168..336]);
+    assert_eq!(&blocks[2][..], &expected[336..504]);
+    assert_eq!(xof.squeezed(), 504);
+}
+
+#[cfg(feature = "std")]
+#[test]
+fn test_xof_stream_read() {
+    use std::io::Read;
+
+    let mut expected = [0; 300];
+    stream().fill(&mut expected);
+
+    let mut buf = Vec::new();
+    Read::take(stream(), 300).read_to_end(&mut buf).unwrap();
+    assert_eq!(buf, &expected[..]);
+}
+
+#[cfg(feature = "alloc")]
+#[test]
+fn test_xof_stream_take() {
+    let mut expected = [0; 300];
+    stream().fill(&mut expected);
+
+    let mut xof = stream();
+    xof.skip(200);
+    assert_eq!(xof.take(100), &expected[200..]);
+}